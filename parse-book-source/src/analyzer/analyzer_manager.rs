@@ -1,7 +1,18 @@
-use std::{collections::HashMap, sync::LazyLock};
-
-use super::{json::value_to_string, Analyzer, AnalyzerType, Analyzers, SingleRule};
-use crate::{utils::replace_all, Result};
+use std::{
+    collections::HashMap,
+    sync::{Arc, LazyLock},
+};
+
+use super::{
+    js::{self, js_value_to_string, split_js, RulePart},
+    json::value_to_string,
+    Analyzer, AnalyzerType, Analyzers, JsonPathAnalyzer, RuleValue, SingleRule,
+};
+use crate::{
+    snippet,
+    utils::{replace_all, Cache},
+    HttpClient, ParseError, Result, RuleContext,
+};
 use anyhow::anyhow;
 use regex::Regex;
 use serde_json::Value;
@@ -15,11 +26,40 @@ static SPLIT_RULE: LazyLock<Regex> = LazyLock::new(|| {
 static EXPRESSION: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\{\{(.+?)\}\}").unwrap());
 static PUT_RULE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"@put:\{(.+?):(.+?)\}").unwrap());
 static GET_RULE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"@get:\{(.+?)\}").unwrap());
+static HTTP_RULE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"@http:").unwrap());
+
+/// 错误信息中的脚本步骤
+fn js_step(code: &str) -> String {
+    format!("<js>{}</js>", snippet(code))
+}
+
+/// 规则调试时每一步的结果，见 [AnalyzerManager::debug]
+#[derive(Debug, Clone)]
+pub struct RuleStep {
+    /// 这一步的规则
+    pub rule: String,
+    /// 解析器类型，脚本为 None
+    pub analyzer: Option<AnalyzerType>,
+    /// 这一步得到的元素，出错时为错误原因
+    pub result: std::result::Result<Vec<String>, String>,
+}
+
+/// 调试时只显示错误原因，步骤和输入已经在 [RuleStep] 中
+fn step_error(e: ParseError) -> String {
+    match e {
+        ParseError::Rule(diagnostic) => diagnostic.cause.to_string(),
+        e => e.to_string(),
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct AnalyzerManager {
     pub analyzers: Vec<Analyzers>,
     pub variables: HashMap<String, String>,
+    /// 脚本中的 baseUrl
+    pub base_url: String,
+    /// 拆分后的规则缓存，克隆的解析器共用
+    rules: Arc<Cache<Arc<Vec<SingleRule>>>>,
 }
 
 impl AnalyzerManager {
@@ -28,9 +68,20 @@ impl AnalyzerManager {
             analyzers: vec![
                 Analyzers::new(r"^@css:", None, AnalyzerType::Html)?,
                 Analyzers::new(r"^@json:|^\$", Some(r"^@json:"), AnalyzerType::JsonPath)?,
+                Analyzers::new(r"^@xpath:|^//", Some(r"^@xpath:"), AnalyzerType::XPath)?,
+                Analyzers::new(
+                    r"^@regex:|^@regexp:|^@match:",
+                    Some(r"^@regexp?:|^@match:"),
+                    AnalyzerType::Regex,
+                )?,
+                Analyzers::new(r"^@encode:", Some(r"^@encode:"), AnalyzerType::Encode)?,
+                Analyzers::new(r"^@decode:", Some(r"^@decode:"), AnalyzerType::Decode)?,
+                Analyzers::new(r"^@http:", Some(r"^@http:"), AnalyzerType::Http)?,
                 Analyzers::new("", None, AnalyzerType::Default)?,
             ],
             variables: HashMap::new(),
+            base_url: String::new(),
+            rules: Arc::new(Cache::new()),
         })
     }
 
@@ -45,8 +96,53 @@ impl AnalyzerManager {
             .unwrap()
     }
 
-    pub fn split_rule_resolve(&self, rule: &str) -> Result<Vec<SingleRule>> {
-        let rule_match = SPLIT_RULE.find_iter(rule).collect::<Vec<_>>();
+    /// 预先编译规则，检查每一步规则是否有效
+    /// 链接模板和变量规则在运行时才能确定，只检查其中的脚本之外的部分
+    pub fn compile(&self, rule: &str) -> Result<()> {
+        let rules = match split_js(rule) {
+            Some(parts) => parts
+                .into_iter()
+                .filter_map(|part| match part {
+                    RulePart::Rule(rule) => Some(rule),
+                    RulePart::Js(_) => None,
+                })
+                .collect(),
+            None => vec![rule.to_string()],
+        };
+
+        for rule in rules {
+            let rule = rule.trim();
+            if rule.is_empty()
+                || rule.contains("{{")
+                || PUT_RULE.is_match(rule)
+                || GET_RULE.is_match(rule)
+            {
+                continue;
+            }
+            for single_rule in self.split_rule_resolve(rule)?.iter() {
+                single_rule.analyzer.validate(&single_rule.rule)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 拆分规则，结果会被缓存
+    pub fn split_rule_resolve(&self, rule: &str) -> Result<Arc<Vec<SingleRule>>> {
+        self.rules
+            .get_or_try_insert(rule, || Ok(Arc::new(self._split_rule_resolve(rule)?)))
+    }
+
+    fn _split_rule_resolve(&self, rule: &str) -> Result<Vec<SingleRule>> {
+        // {{}} 表达式中的规则不拆分，例如 @http:/api?id={{@css:#id@text}}
+        let expressions = EXPRESSION.find_iter(rule).collect::<Vec<_>>();
+        let rule_match = SPLIT_RULE
+            .find_iter(rule)
+            .filter(|m| {
+                !expressions
+                    .iter()
+                    .any(|e| e.start() < m.start() && m.start() < e.end())
+            })
+            .collect::<Vec<_>>();
         let mut rule_list: Vec<SingleRule> = vec![];
         let mut end = rule.len();
 
@@ -82,11 +178,12 @@ impl AnalyzerManager {
         Ok(rule_list)
     }
 
-    fn _get_elements(analyzer: &dyn Analyzer, rule: &str) -> Result<Vec<String>> {
+    /// 执行列表规则，支持 `&&` 合并和 `||` 取第一个非空结果
+    fn _get_elements<T>(rule: &str, get: &impl Fn(&str) -> Result<Vec<T>>) -> Result<Vec<T>> {
         if rule.contains("&&") {
             let mut res = vec![];
             for simple_rule in rule.split("&&") {
-                let mut r = Self::_get_elements(analyzer, simple_rule)?;
+                let mut r = Self::_get_elements(simple_rule, get)?;
 
                 if !r.is_empty() {
                     res.append(&mut r);
@@ -95,29 +192,106 @@ impl AnalyzerManager {
             return Ok(res);
         } else if rule.contains("||") {
             for simple_rule in rule.split("||") {
-                let r = Self::_get_elements(analyzer, simple_rule)?;
+                let r = Self::_get_elements(simple_rule, get)?;
 
                 if !r.is_empty() {
                     return Ok(r);
                 };
             }
         }
-        analyzer.get_elements(rule)
+        get(rule)
     }
 
-    pub fn get_element(&self, rule: &str, data: &str) -> Result<Vec<String>> {
-        let mut temp = data.to_string();
+    /// 对上一步结果中的每一项执行一步列表规则，JSONPath 规则的结果保持为 JSON 值
+    fn apply_elements(single_rule: &SingleRule, value: RuleValue) -> Result<RuleValue> {
+        if single_rule.analyzer == AnalyzerType::JsonPath {
+            let items = match value {
+                RuleValue::Json(list) => list,
+                other => {
+                    let mut list = vec![];
+                    for item in other.into_strings() {
+                        list.push(
+                            serde_json::from_str(&item)
+                                .map_err(ParseError::from)
+                                .step(single_rule, &item)?,
+                        );
+                    }
+                    list
+                }
+            };
+
+            let mut res = vec![];
+            for item in items {
+                res.append(
+                    &mut Self::_get_elements(&single_rule.rule, &|rule| {
+                        JsonPathAnalyzer::values(&item, rule)
+                    })
+                    .map_err(|e| e.with_step(single_rule, &item.to_string()))?,
+                );
+            }
+            return Ok(RuleValue::Json(res));
+        }
 
-        for single_rule in self.split_rule_resolve(rule)? {
-            let analyzer = single_rule.analyzer.parse_to_analyzer(&temp)?;
-            temp = Self::_get_elements(analyzer.as_ref(), &single_rule.rule)?
-                .join("_______split_______");
+        let mut res = vec![];
+        for item in value.into_strings() {
+            res.append(&mut Self::elements_of(single_rule, &item).step(single_rule, &item)?);
         }
+        Ok(RuleValue::Strings(res))
+    }
+
+    fn elements_of(single_rule: &SingleRule, data: &str) -> Result<Vec<String>> {
+        let analyzer = single_rule.analyzer.parse_cached(data)?;
+        Self::_get_elements(&single_rule.rule, &|rule| analyzer.get_elements(rule))
+    }
+
+    /// 对上一步的字符串结果执行一步规则
+    fn string_of(single_rule: &SingleRule, data: &str) -> Result<String> {
+        let analyzer = single_rule.analyzer.parse_cached(data)?;
+        let res = Self::_get_string(single_rule, analyzer.as_ref(), &single_rule.rule)?;
+        single_rule.replace_content(&res)
+    }
+
+    fn eval_js(&mut self, code: &str, result: Value, extra: Option<&Value>) -> Result<Value> {
+        js::eval_js(code, result, &self.base_url, extra, &mut self.variables)
+    }
+
+    /// 执行列表规则中的脚本，见 [RuleValue::into_js_result] 和 [RuleValue::from_js_result]
+    fn eval_js_elements(&mut self, code: &str, value: RuleValue) -> Result<RuleValue> {
+        let input = value.into_js_result();
+        let result = self
+            .eval_js(code, input.clone(), None)
+            .map_err(|e| e.with_step(js_step(code), &js_value_to_string(&input)))?;
+        Ok(RuleValue::from_js_result(result))
+    }
 
-        Ok(temp
-            .split("_______split_______")
-            .map(|s| s.to_string())
-            .collect())
+    pub fn get_element(&mut self, rule: &str, data: &str) -> Result<Vec<String>> {
+        Ok(self
+            .get_value(rule, RuleValue::Text(data.to_string()))?
+            .into_strings())
+    }
+
+    /// 执行列表规则，返回带类型的结果
+    pub fn get_value(&mut self, rule: &str, value: RuleValue) -> Result<RuleValue> {
+        if let Some(parts) = split_js(rule) {
+            let mut temp = value;
+            for part in parts {
+                temp = match part {
+                    RulePart::Rule(rule) => self._get_element(&rule, temp)?,
+                    RulePart::Js(code) => self.eval_js_elements(&code, temp)?,
+                };
+            }
+            return Ok(temp);
+        }
+
+        self._get_element(rule, value)
+    }
+
+    fn _get_element(&self, rule: &str, value: RuleValue) -> Result<RuleValue> {
+        let mut temp = value;
+        for single_rule in self.split_rule_resolve(rule)?.iter() {
+            temp = Self::apply_elements(single_rule, temp)?;
+        }
+        Ok(temp)
     }
 
     fn _get_string(
@@ -195,6 +369,32 @@ impl AnalyzerManager {
     }
 
     pub fn get_string(&mut self, rule: &str, data: &str, extra: Option<Value>) -> Result<String> {
+        if let Some(parts) = split_js(rule) {
+            let mut temp = data.to_string();
+            for part in parts {
+                temp = match part {
+                    RulePart::Rule(rule) => {
+                        self.get_string_without_js(&rule, &temp, extra.clone())?
+                    }
+                    RulePart::Js(code) => js_value_to_string(
+                        &self
+                            .eval_js(&code, Value::String(temp.clone()), extra.as_ref())
+                            .step(js_step(&code), &temp)?,
+                    ),
+                };
+            }
+            return Ok(temp);
+        }
+
+        self.get_string_without_js(rule, data, extra)
+    }
+
+    fn get_string_without_js(
+        &mut self,
+        rule: &str,
+        data: &str,
+        extra: Option<Value>,
+    ) -> Result<String> {
         if rule.is_empty() {
             return Ok("".to_string());
         }
@@ -209,30 +409,265 @@ impl AnalyzerManager {
         let p_left = new_rule.rfind("{{");
         let p_right = new_rule.rfind("}}");
 
-        if p_left.is_some() && p_right.is_some() {
-            let left = p_left.unwrap();
-            let right = p_right.unwrap();
-
+        if let (Some(left), Some(right)) = (p_left, p_right) {
             if left < right {
                 return replace_all(&EXPRESSION, &new_rule, |captures| {
                     let sub_rule = captures.get(1).map(|m| m.as_str().trim()).unwrap_or("");
-                    if extra.is_some() {
-                        if let Some(extra_value) = extra.as_ref().unwrap().get(sub_rule) {
-                            return value_to_string(extra_value);
+                    if let Some(extra_value) = extra.as_ref().and_then(|e| e.get(sub_rule)) {
+                        return value_to_string(extra_value);
+                    }
+
+                    // 以额外参数作为数据继续执行后续规则，例如 {{key@encode:base64}}
+                    if let Some((name, rest)) = sub_rule.split_once('@') {
+                        if let Some(extra_value) = extra.as_ref().and_then(|e| e.get(name.trim())) {
+                            let value = value_to_string(extra_value)?;
+                            return self.get_string(&format!("@{}", rest), &value, None);
                         }
                     }
-                    self.get_string(sub_rule, data, None)
+
+                    let result = self.get_string(sub_rule, data, None);
+
+                    // 开启 js feature 时，规则解析失败或者结果为空的表达式再按脚本执行，
+                    // 例如 {{(page - 1) * 20}}，脚本也失败时返回规则的结果
+                    if cfg!(feature = "js")
+                        && extra.is_some()
+                        && !sub_rule.starts_with(['@', '$', '/'])
+                        && result.as_ref().map_or(true, |value| value.is_empty())
+                    {
+                        if let Ok(value) =
+                            self.eval_js(sub_rule, Value::String(data.to_string()), extra.as_ref())
+                        {
+                            return Ok(js_value_to_string(&value));
+                        }
+                    }
+                    result
                 });
             }
         }
 
-        // 处理普通规则
+        // 处理普通规则，字符串规则的每一步直接以上一步的字符串结果作为内容，不使用 RuleValue
         let mut temp = data.to_string();
-        for single_rule in self.split_rule_resolve(&new_rule)? {
-            let analyzer = single_rule.analyzer.parse_to_analyzer(&temp)?;
+        for single_rule in self.split_rule_resolve(&new_rule)?.iter() {
+            temp = Self::string_of(single_rule, &temp).step(single_rule, &temp)?;
+        }
+        Ok(temp)
+    }
+
+    /// 请求 `@http:` 规则中的链接
+    /// 规则为空时以上一步的结果作为链接，否则规则本身是链接模板，例如 `@http:/api/toc?id={{$.id}}`
+    async fn fetch(
+        &mut self,
+        single_rule: &SingleRule,
+        data: &str,
+        extra: Option<Value>,
+        http_client: &HttpClient,
+    ) -> Result<String> {
+        let url = if single_rule.rule.trim().is_empty() {
+            data.trim().to_string()
+        } else {
+            self.get_string(single_rule.rule.trim(), data, extra)?
+        };
 
-            temp = Self::_get_string(&single_rule, analyzer.as_ref(), &single_rule.rule)?;
-            temp = single_rule.replace_content(&temp)?;
+        if url.is_empty() {
+            return Err(anyhow!("the url of @http: rule is empty").into());
+        }
+
+        let res = http_client.get_text(&url).await?;
+        single_rule.replace_content(&res)
+    }
+
+    /// 按列表规则逐步执行，返回每一步的结果，用于调试规则
+    /// 某一步出错时不再执行后面的步骤，`@http:` 需要网络请求，调试时不执行
+    pub fn debug(&mut self, rule: &str, data: &str) -> Vec<RuleStep> {
+        let parts = split_js(rule).unwrap_or_else(|| vec![RulePart::Rule(rule.to_string())]);
+        let mut steps = vec![];
+        let mut temp = RuleValue::Text(data.to_string());
+
+        for part in parts {
+            let results = match part {
+                RulePart::Rule(rule) => {
+                    let single_rules = match self.split_rule_resolve(&rule) {
+                        Ok(single_rules) => single_rules,
+                        Err(e) => {
+                            steps.push(RuleStep {
+                                rule,
+                                analyzer: None,
+                                result: Err(step_error(e)),
+                            });
+                            return steps;
+                        }
+                    };
+
+                    let mut results = vec![];
+                    for single_rule in single_rules.iter() {
+                        let result = Self::apply_elements(single_rule, temp.clone());
+                        if let Ok(value) = &result {
+                            temp = value.clone();
+                        }
+                        let failed = result.is_err();
+                        results.push((
+                            single_rule.to_string(),
+                            Some(single_rule.analyzer.clone()),
+                            result,
+                        ));
+                        if failed {
+                            break;
+                        }
+                    }
+                    results
+                }
+                RulePart::Js(code) => {
+                    let result = self.eval_js_elements(&code, temp.clone());
+                    if let Ok(value) = &result {
+                        temp = value.clone();
+                    }
+                    vec![(js_step(&code), None, result)]
+                }
+            };
+
+            for (rule, analyzer, result) in results {
+                let failed = result.is_err();
+                steps.push(RuleStep {
+                    rule,
+                    analyzer,
+                    result: result.map(RuleValue::into_strings).map_err(step_error),
+                });
+                if failed {
+                    return steps;
+                }
+            }
+        }
+        steps
+    }
+
+    /// 支持 `@http:` 规则的 [AnalyzerManager::get_element]
+    pub async fn get_element_async(
+        &mut self,
+        rule: &str,
+        data: &str,
+        http_client: &HttpClient,
+    ) -> Result<Vec<String>> {
+        Ok(self
+            .get_value_async(rule, RuleValue::Text(data.to_string()), http_client)
+            .await?
+            .into_strings())
+    }
+
+    /// 支持 `@http:` 规则的 [AnalyzerManager::get_value]
+    pub async fn get_value_async(
+        &mut self,
+        rule: &str,
+        value: RuleValue,
+        http_client: &HttpClient,
+    ) -> Result<RuleValue> {
+        if !HTTP_RULE.is_match(rule) {
+            return self.get_value(rule, value);
+        }
+
+        if let Some(parts) = split_js(rule) {
+            let mut temp = value;
+            for part in parts {
+                temp = match part {
+                    RulePart::Rule(rule) => {
+                        self.get_value_with_http(&rule, temp, http_client).await?
+                    }
+                    RulePart::Js(code) => self.eval_js_elements(&code, temp)?,
+                };
+            }
+            return Ok(temp);
+        }
+
+        self.get_value_with_http(rule, value, http_client).await
+    }
+
+    async fn get_value_with_http(
+        &mut self,
+        rule: &str,
+        value: RuleValue,
+        http_client: &HttpClient,
+    ) -> Result<RuleValue> {
+        if !HTTP_RULE.is_match(rule) {
+            return self._get_element(rule, value);
+        }
+
+        let mut temp = value;
+        for single_rule in self.split_rule_resolve(rule)?.iter() {
+            temp = if single_rule.analyzer == AnalyzerType::Http {
+                // 上一步的每一项都请求一次
+                let mut res = vec![];
+                for item in temp.into_texts() {
+                    res.push(
+                        self.fetch(single_rule, &item, None, http_client)
+                            .await
+                            .step(single_rule, &item)?,
+                    );
+                }
+                RuleValue::Strings(res)
+            } else {
+                Self::apply_elements(single_rule, temp)?
+            };
+        }
+        Ok(temp)
+    }
+
+    /// 支持 `@http:` 规则的 [AnalyzerManager::get_string]
+    pub async fn get_string_async(
+        &mut self,
+        rule: &str,
+        data: &str,
+        extra: Option<Value>,
+        http_client: &HttpClient,
+    ) -> Result<String> {
+        if !HTTP_RULE.is_match(rule) {
+            return self.get_string(rule, data, extra);
+        }
+
+        if let Some(parts) = split_js(rule) {
+            let mut temp = data.to_string();
+            for part in parts {
+                temp = match part {
+                    RulePart::Rule(rule) => {
+                        self.get_string_with_http(&rule, &temp, extra.clone(), http_client)
+                            .await?
+                    }
+                    RulePart::Js(code) => js_value_to_string(
+                        &self
+                            .eval_js(&code, Value::String(temp.clone()), extra.as_ref())
+                            .step(js_step(&code), &temp)?,
+                    ),
+                };
+            }
+            return Ok(temp);
+        }
+
+        self.get_string_with_http(rule, data, extra, http_client)
+            .await
+    }
+
+    async fn get_string_with_http(
+        &mut self,
+        rule: &str,
+        data: &str,
+        extra: Option<Value>,
+        http_client: &HttpClient,
+    ) -> Result<String> {
+        if !HTTP_RULE.is_match(rule) {
+            return self.get_string_without_js(rule, data, extra);
+        }
+
+        let new_rule = self.put_variable(rule, data)?;
+        let new_rule = self.get_variable(&new_rule)?;
+
+        // 这里先拆分规则，表达式只在 @http: 的链接模板中处理
+        let mut temp = data.to_string();
+        for single_rule in self.split_rule_resolve(&new_rule)?.iter() {
+            temp = if single_rule.analyzer == AnalyzerType::Http {
+                self.fetch(single_rule, &temp, extra.clone(), http_client)
+                    .await
+                    .step(single_rule, &temp)?
+            } else {
+                Self::string_of(single_rule, &temp).step(single_rule, &temp)?
+            };
         }
         Ok(temp)
     }
@@ -260,6 +695,178 @@ mod tests {
         assert_eq!(res.unwrap(), "https://www.xmkanshu.com/service/getContent?fr=smsstg&v=4&uid=B197589CF54DC527538FADCAE6BDBC78&urbid=%2Fbook_95_0&bkid=123&crid=300&pg=1");
     }
 
+    #[test]
+    fn test_analyzer_manager_codec() {
+        let mut analyzer_manager = AnalyzerManager::new().unwrap();
+        let data = r#"{"name":"5paX56C0"}"#;
+
+        let name = analyzer_manager
+            .get_string("$.name@decode:base64@encode:url-gbk", data, None)
+            .unwrap();
+        assert_eq!(name, "%B6%B7%C6%C6");
+
+        let url = analyzer_manager
+            .get_string(
+                "/search?q={{key@encode:base64}}&page={{page}}",
+                "",
+                Some(json!({
+                    "key": "斗破",
+                    "page": 1,
+                })),
+            )
+            .unwrap();
+        assert_eq!(url, "/search?q=5paX56C0&page=1");
+    }
+
+    #[cfg(feature = "js")]
+    #[test]
+    fn test_analyzer_manager_js() {
+        let mut analyzer_manager = AnalyzerManager::new().unwrap();
+        analyzer_manager.base_url = "https://example.com".into();
+        let data = r#"{"list":[{"id":1,"name":" 第一章 "},{"id":2,"name":"第二章"}]}"#;
+
+        let list = analyzer_manager
+            .get_element("$.list[*]<js>result.slice(1)</js>", data)
+            .unwrap();
+        assert_eq!(list, vec![r#"{"id":2,"name":"第二章"}"#]);
+
+        // JSON 列表作为对象传给脚本
+        let list = analyzer_manager
+            .get_element(
+                "$.list[*]<js>result.map(function (item) { return item.id + ':' + item.name.trim(); })</js>",
+                data,
+            )
+            .unwrap();
+        assert_eq!(list, vec!["1:第一章", "2:第二章"]);
+
+        // 只有一项时也是数组
+        let list = analyzer_manager
+            .get_element(
+                "$.list[1]<js>result.map(function (item) { return item.name + result.length; })</js>",
+                data,
+            )
+            .unwrap();
+        assert_eq!(list, vec!["第二章1"]);
+
+        let name = analyzer_manager
+            .get_string("$.list[0].name@js:result.trim()", data, None)
+            .unwrap();
+        assert_eq!(name, "第一章");
+
+        let url = analyzer_manager
+            .get_string(
+                "@put:{bid:$.list[1].id}<js>baseUrl + '/book/' + java.get('bid')</js>",
+                data,
+                None,
+            )
+            .unwrap();
+        assert_eq!(url, "https://example.com/book/2");
+
+        let url = analyzer_manager
+            .get_string(
+                "/search?key={{key}}&start={{(page - 1) * 20}}",
+                "",
+                Some(json!({ "key": "abc", "page": 3 })),
+            )
+            .unwrap();
+        assert_eq!(url, "/search?key=abc&start=40");
+
+        // 表达式中的默认规则仍然由规则解析，不按脚本执行
+        let url = analyzer_manager
+            .get_string(
+                "{{class.next@href}}",
+                r#"<div><a class="next" href="/book/1_2.html">下一页</a></div>"#,
+                Some(json!({ "index": 1 })),
+            )
+            .unwrap();
+        assert_eq!(url, "/book/1_2.html");
+    }
+
+    #[cfg(not(feature = "js"))]
+    #[test]
+    fn test_analyzer_manager_js_disabled() {
+        let mut analyzer_manager = AnalyzerManager::new().unwrap();
+        assert!(analyzer_manager
+            .get_string("$.name<js>result.trim()</js>", r#"{"name":"a"}"#, None)
+            .is_err());
+    }
+
+    #[test]
+    fn test_analyzer_manager_regex() {
+        let mut analyzer_manager = AnalyzerManager::new().unwrap();
+        let data = r#"<script>var list = [{"name":"第一章","url":"/1.html"},{"name":"第二章","url":"/2.html"}];</script>"#;
+
+        let list = analyzer_manager
+            .get_element(r#"@regex:\{"name":"[^"]+","url":"[^"]+"\}"#, data)
+            .unwrap();
+        assert_eq!(list.len(), 2);
+
+        let name = analyzer_manager
+            .get_string("$.name", &list[1], None)
+            .unwrap();
+        assert_eq!(name, "第二章");
+
+        let url = analyzer_manager
+            .get_string(r#"@match:"url":"([^"]+)"@@$1"#, &list[0], None)
+            .unwrap();
+        assert_eq!(url, "/1.html");
+    }
+
+    #[tokio::test]
+    async fn test_analyzer_manager_http() {
+        use crate::{
+            utils::test_server::{serve, TestResponse},
+            HttpConfig,
+        };
+
+        let base_url = serve(|req| match req.path.as_str() {
+            "/book/42/toc" => {
+                TestResponse::ok(r#"{"chapters":[{"name":"第一章"},{"name":"第二章"}]}"#)
+            }
+            "/token?id=42" => TestResponse::ok("abc"),
+            _ => TestResponse::status(404),
+        });
+        let http_client = HttpClient::new(&base_url, &HttpConfig::default()).unwrap();
+        let mut analyzer_manager = AnalyzerManager::new().unwrap();
+        let data = r#"<div id="info" data-id="42" data-toc="/book/42/toc"></div>"#;
+
+        let list = analyzer_manager
+            .get_element_async(
+                "@http:/book/{{@css:#info@data-id}}/toc@json:$.chapters[*]",
+                data,
+                &http_client,
+            )
+            .await
+            .unwrap();
+        assert_eq!(list.len(), 2);
+
+        let name = analyzer_manager
+            .get_string_async(
+                "@css:#info@data-toc@http:@json:$.chapters[1].name",
+                data,
+                None,
+                &http_client,
+            )
+            .await
+            .unwrap();
+        assert_eq!(name, "第二章");
+
+        let token = analyzer_manager
+            .get_string_async(
+                "@http:/token?id={{@css:#info@data-id}}",
+                data,
+                None,
+                &http_client,
+            )
+            .await
+            .unwrap();
+        assert_eq!(token, "abc");
+
+        assert!(analyzer_manager
+            .get_string("@http:/token?id=42", data, None)
+            .is_err());
+    }
+
     #[test]
     fn test_analyzer_manager_get_analyzer() {
         let analyzer_manager = AnalyzerManager::new().unwrap();
@@ -272,5 +879,99 @@ mod tests {
 
         let analyzer = analyzer_manager.get_analyzer("@css:div h1 a[href]");
         assert_eq!(analyzer.analyzer, AnalyzerType::Html);
+
+        let analyzer = analyzer_manager.get_analyzer("@xpath://div[@id='intro']/text()");
+        assert_eq!(analyzer.analyzer, AnalyzerType::XPath);
+
+        let analyzer = analyzer_manager.get_analyzer("//div[@id='intro']/text()");
+        assert_eq!(analyzer.analyzer, AnalyzerType::XPath);
+
+        let analyzer = analyzer_manager.get_analyzer(r"@regex:bookId = (\d+)@@$1");
+        assert_eq!(analyzer.analyzer, AnalyzerType::Regex);
+    }
+
+    #[test]
+    fn test_analyzer_manager_compile() {
+        let analyzer_manager = AnalyzerManager::new().unwrap();
+        assert!(analyzer_manager
+            .compile("class.list@tag.li.0@tag.a@href##\\s+")
+            .is_ok());
+        assert!(analyzer_manager
+            .compile("$.list[*]<js>result.slice(1)</js>@css:a@text")
+            .is_ok());
+        // 链接模板在运行时才能确定
+        assert!(analyzer_manager.compile("/search?q={{key}}").is_ok());
+
+        assert!(analyzer_manager.compile("@css:div[@text").is_err());
+        assert!(analyzer_manager
+            .compile("@css:a@text||@css:[[@href")
+            .is_err());
+        assert!(analyzer_manager.compile(r"@regex:(\d+").is_err());
+        assert!(analyzer_manager.compile("$.name##(abc").is_err());
+        assert!(analyzer_manager.compile("//div[").is_err());
+        assert!(analyzer_manager.compile("@encode:unknown").is_err());
+
+        // 无效的选择器返回错误而不是 panic
+        let mut analyzer_manager = analyzer_manager;
+        assert!(analyzer_manager
+            .get_string("@css:div[@text", "<div></div>", None)
+            .is_err());
+    }
+
+    #[test]
+    fn test_analyzer_manager_chained_elements() {
+        let mut analyzer_manager = AnalyzerManager::new().unwrap();
+
+        let data =
+            r#"{"data":[{"chapters":[{"name":"1"},{"name":"2"}]},{"chapters":[{"name":"3"}]}]}"#;
+        let list = analyzer_manager
+            .get_value("$.data[*]@json:$.chapters[*]", RuleValue::Text(data.into()))
+            .unwrap();
+        assert_eq!(
+            list,
+            RuleValue::Json(vec![
+                json!({"name": "1"}),
+                json!({"name": "2"}),
+                json!({"name": "3"})
+            ])
+        );
+
+        let data =
+            r#"<ul><li><a href="/1">a</a><a href="/2">b</a></li><li><a href="/3">c</a></li></ul>"#;
+        let list = analyzer_manager.get_element("@css:li@css:a", data).unwrap();
+        assert_eq!(list.len(), 3);
+        assert_eq!(
+            analyzer_manager
+                .get_string("@css:a@href", &list[2], None)
+                .unwrap(),
+            "/3"
+        );
+
+        // 内容中包含旧的分隔符也不会被拆开
+        let data = r#"<ul><li>_______split_______</li><li>b</li></ul>"#;
+        let list = analyzer_manager.get_element("@css:li", data).unwrap();
+        assert_eq!(list.len(), 2);
+        assert!(list[0].contains("_______split_______"));
+    }
+
+    #[test]
+    fn test_analyzer_manager_debug() {
+        let mut analyzer_manager = AnalyzerManager::new().unwrap();
+        let data = r#"<ul><li><a href="/1">a</a></li><li><a href="/2">b</a></li></ul>"#;
+
+        let steps = analyzer_manager.debug("@css:li@css:a", data);
+        assert_eq!(steps.len(), 2);
+        assert_eq!(steps[0].rule, "@css:li");
+        assert_eq!(steps[0].analyzer, Some(AnalyzerType::Html));
+        assert_eq!(steps[0].result.as_ref().unwrap().len(), 2);
+        assert_eq!(
+            steps[1].result.as_ref().unwrap(),
+            &vec![r#"<a href="/1">a</a>"#, r#"<a href="/2">b</a>"#]
+        );
+
+        // 出错时不再执行后面的步骤
+        let steps = analyzer_manager.debug("@css:li@json:$.name@css:a", data);
+        assert_eq!(steps.len(), 2);
+        assert!(steps[1].result.is_err());
     }
 }
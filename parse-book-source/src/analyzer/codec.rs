@@ -0,0 +1,250 @@
+use super::Analyzer;
+use crate::Result;
+use anyhow::anyhow;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use encoding_rs::Encoding;
+use percent_encoding::{percent_decode_str, percent_encode, AsciiSet, NON_ALPHANUMERIC};
+
+/// 与 JavaScript 的 encodeURIComponent 保持一致
+const URI_COMPONENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'!')
+    .remove(b'~')
+    .remove(b'*')
+    .remove(b'\'')
+    .remove(b'(')
+    .remove(b')');
+
+/// 根据名称获取字符集，例如 `gbk`、`gb2312`
+pub fn get_encoding(label: &str) -> Result<&'static Encoding> {
+    Encoding::for_label(label.trim().as_bytes())
+        .ok_or(anyhow!("unsupported charset: {}", label).into())
+}
+
+/// 按指定字符集进行 url 编码，例如 `utf-8`、`gbk`
+pub fn url_encode(content: &str, charset: &str) -> Result<String> {
+    let (bytes, _, _) = get_encoding(charset)?.encode(content);
+    Ok(percent_encode(&bytes, URI_COMPONENT).to_string())
+}
+
+/// 按指定字符集进行 url 解码
+pub fn url_decode(content: &str, charset: &str) -> Result<String> {
+    let bytes = percent_decode_str(&content.replace('+', " ")).collect::<Vec<u8>>();
+    let (res, _, _) = get_encoding(charset)?.decode(&bytes);
+    Ok(res.into_owned())
+}
+
+fn html_entity_encode(content: &str) -> String {
+    let mut res = String::with_capacity(content.len());
+    for c in content.chars() {
+        match c {
+            '&' => res.push_str("&amp;"),
+            '<' => res.push_str("&lt;"),
+            '>' => res.push_str("&gt;"),
+            '"' => res.push_str("&quot;"),
+            '\'' => res.push_str("&#39;"),
+            c => res.push(c),
+        }
+    }
+    res
+}
+
+fn html_entity_decode(content: &str) -> String {
+    let mut res = String::with_capacity(content.len());
+    let mut rest = content;
+
+    while let Some(start) = rest.find('&') {
+        res.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let decoded = rest.find(';').filter(|end| *end <= 10).and_then(|end| {
+            let entity = &rest[1..end];
+            let c = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some('\u{a0}'),
+                _ => {
+                    let code = match entity.strip_prefix("#x").or(entity.strip_prefix("#X")) {
+                        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                        None => entity.strip_prefix('#').and_then(|num| num.parse().ok()),
+                    };
+                    code.and_then(char::from_u32)
+                }
+            };
+            c.map(|c| (c, end))
+        });
+
+        match decoded {
+            Some((c, end)) => {
+                res.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                res.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    res.push_str(rest);
+    res
+}
+
+fn hex_decode(content: &str) -> Result<Vec<u8>> {
+    let content = content.trim();
+    if !content.len().is_multiple_of(2) {
+        return Err(anyhow!("invalid hex string: {}", content).into());
+    }
+    (0..content.len())
+        .step_by(2)
+        .map(|i| {
+            content
+                .get(i..i + 2)
+                .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                .ok_or(anyhow!("invalid hex string: {}", content).into())
+        })
+        .collect()
+}
+
+/// 编码，支持 `url`、`url-gbk`（或其他字符集 `url-<charset>`）、`base64`、`hex`、`html-entity`
+pub fn encode(mode: &str, content: &str) -> Result<String> {
+    match mode.trim() {
+        "url" => url_encode(content, "utf-8"),
+        "base64" => Ok(STANDARD.encode(content)),
+        "hex" => Ok(content.bytes().map(|b| format!("{:02x}", b)).collect()),
+        "html-entity" => Ok(html_entity_encode(content)),
+        mode => match mode.strip_prefix("url-") {
+            Some(charset) => url_encode(content, charset),
+            None => Err(anyhow!("unsupported encode mode: {}", mode).into()),
+        },
+    }
+}
+
+/// 解码，支持的模式同 [encode]
+pub fn decode(mode: &str, content: &str) -> Result<String> {
+    match mode.trim() {
+        "url" => url_decode(content, "utf-8"),
+        "base64" => Ok(String::from_utf8_lossy(
+            &STANDARD
+                .decode(content.trim())
+                .map_err(|e| anyhow!("invalid base64 string: {}", e))?,
+        )
+        .into_owned()),
+        "hex" => Ok(String::from_utf8_lossy(&hex_decode(content)?).into_owned()),
+        "html-entity" => Ok(html_entity_decode(content)),
+        mode => match mode.strip_prefix("url-") {
+            Some(charset) => url_decode(content, charset),
+            None => Err(anyhow!("unsupported decode mode: {}", mode).into()),
+        },
+    }
+}
+
+/// `@encode:` 规则，对上一步的结果进行编码
+pub struct EncodeAnalyzer {
+    content: String,
+}
+
+impl Analyzer for EncodeAnalyzer {
+    fn parse(content: &str) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self {
+            content: content.to_string(),
+        })
+    }
+
+    fn validate(rule: &str) -> Result<()>
+    where
+        Self: Sized,
+    {
+        encode(rule, "").map(|_| ())
+    }
+
+    fn get_string(&self, rule: &str) -> Result<String> {
+        encode(rule, &self.content)
+    }
+
+    fn get_string_list(&self, rule: &str) -> Result<Vec<String>> {
+        Ok(vec![self.get_string(rule)?])
+    }
+
+    fn get_elements(&self, rule: &str) -> Result<Vec<String>> {
+        self.get_string_list(rule)
+    }
+}
+
+/// `@decode:` 规则，对上一步的结果进行解码
+pub struct DecodeAnalyzer {
+    content: String,
+}
+
+impl Analyzer for DecodeAnalyzer {
+    fn parse(content: &str) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self {
+            content: content.to_string(),
+        })
+    }
+
+    fn validate(rule: &str) -> Result<()>
+    where
+        Self: Sized,
+    {
+        decode(rule, "").map(|_| ())
+    }
+
+    fn get_string(&self, rule: &str) -> Result<String> {
+        decode(rule, &self.content)
+    }
+
+    fn get_string_list(&self, rule: &str) -> Result<Vec<String>> {
+        Ok(vec![self.get_string(rule)?])
+    }
+
+    fn get_elements(&self, rule: &str) -> Result<Vec<String>> {
+        self.get_string_list(rule)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(
+            encode("url", "斗破 苍穹&").unwrap(),
+            "%E6%96%97%E7%A0%B4%20%E8%8B%8D%E7%A9%B9%26"
+        );
+        assert_eq!(
+            encode("url-gbk", "斗破苍穹").unwrap(),
+            "%B6%B7%C6%C6%B2%D4%F1%B7"
+        );
+        assert_eq!(encode("base64", "斗破").unwrap(), "5paX56C0");
+        assert_eq!(encode("hex", "ab").unwrap(), "6162");
+        assert_eq!(
+            encode("html-entity", r#"<a href="x">'&'</a>"#).unwrap(),
+            "&lt;a href=&quot;x&quot;&gt;&#39;&amp;&#39;&lt;/a&gt;"
+        );
+        assert!(encode("unknown", "abc").is_err());
+    }
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(decode("url", "%E6%96%97%E7%A0%B4+a").unwrap(), "斗破 a");
+        assert_eq!(decode("url-gbk", "%B6%B7%C6%C6").unwrap(), "斗破");
+        assert_eq!(decode("base64", "5paX56C0").unwrap(), "斗破");
+        assert_eq!(decode("hex", "6162").unwrap(), "ab");
+        assert_eq!(
+            decode("html-entity", "&lt;p&gt;&#26007;&#x7834; &amp; &unknown;").unwrap(),
+            "<p>斗破 & &unknown;"
+        );
+    }
+}
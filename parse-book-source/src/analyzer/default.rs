@@ -1,5 +1,5 @@
 use super::{Analyzer, HtmlAnalyzer};
-use crate::Result;
+use crate::{utils::Cache, Result};
 use anyhow::anyhow;
 use regex::Regex;
 use std::{collections::HashMap, sync::LazyLock};
@@ -11,6 +11,12 @@ pub struct DefaultAnalyzer {
 static CLASS_MAP: LazyLock<HashMap<&'static str, &'static str>> =
     LazyLock::new(|| HashMap::from_iter(vec![("class", "."), ("id", "#"), ("tag", "")]));
 static RANGE_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\[(.*?)\]").unwrap());
+static SELECTORS: LazyLock<Cache<String>> = LazyLock::new(Cache::new);
+
+/// 转换成 CSS 选择器规则，结果会被缓存
+fn compile(rule: &str) -> Result<String> {
+    SELECTORS.get_or_try_insert(rule, || rule_to_selector(rule))
+}
 
 fn rule_to_selector(rule: &str) -> Result<String> {
     let mut selectors = vec![];
@@ -106,13 +112,20 @@ impl Analyzer for DefaultAnalyzer {
         })
     }
 
+    fn validate(rule: &str) -> Result<()>
+    where
+        Self: Sized,
+    {
+        HtmlAnalyzer::validate(&compile(rule)?)
+    }
+
     fn get_string(&self, rule: &str) -> Result<String> {
-        let selector = rule_to_selector(rule)?;
+        let selector = compile(rule)?;
         self.analyzer.get_string(&selector)
     }
 
     fn get_elements(&self, rule: &str) -> Result<Vec<String>> {
-        let selector = rule_to_selector(rule)?;
+        let selector = compile(rule)?;
         self.analyzer.get_elements(&selector)
     }
 }
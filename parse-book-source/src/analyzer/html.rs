@@ -1,7 +1,9 @@
 use super::Analyzer;
+use crate::utils::Cache;
 use anyhow::anyhow;
 use regex::Regex;
-use scraper::{Html, Selector};
+use scraper::{ElementRef, Html, Selector};
+use std::{cell::OnceCell, sync::LazyLock};
 
 fn html_decode(s: &str) -> String {
     let mut result = s.replace("&amp;", "&");
@@ -14,16 +16,31 @@ fn html_decode(s: &str) -> String {
     result
 }
 
+static RE_TAGS: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"</?(?:div|p|br|hr|h\d|article|b|dd|dl|html)[^>]*>").unwrap());
+static RE_COMMENTS: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"<!--[\w\W\r\n]*?-->").unwrap());
+static SELECTORS: LazyLock<Cache<Selector>> = LazyLock::new(Cache::new);
+
 fn get_html_string(html: &str) -> String {
-    let re_tags = Regex::new(r"</?(?:div|p|br|hr|h\d|article|b|dd|dl|html)[^>]*>").unwrap();
-    let re_comments = Regex::new(r"<!--[\w\W\r\n]*?-->").unwrap();
-    let mut result = re_tags.replace_all(html, "\n").to_string();
-    result = re_comments.replace_all(&result, "").to_string();
+    let mut result = RE_TAGS.replace_all(html, "\n").to_string();
+    result = RE_COMMENTS.replace_all(&result, "").to_string();
     html_decode(&result)
 }
 
+/// 编译 CSS 选择器，结果会被缓存
+pub fn compile_selector(selector: &str) -> crate::Result<Selector> {
+    let selector = selector.trim();
+    SELECTORS.get_or_try_insert(selector, || {
+        Selector::parse(selector)
+            .map_err(|e| anyhow!("Invalid selector {}: {}", selector, e).into())
+    })
+}
+
 pub struct HtmlAnalyzer {
     content: String,
+    // 按需解析，同一个解析器的多次调用共用解析结果
+    document: OnceCell<Html>,
+    fragment: OnceCell<Html>,
 }
 
 impl Analyzer for HtmlAnalyzer {
@@ -33,14 +50,30 @@ impl Analyzer for HtmlAnalyzer {
     {
         Ok(Self {
             content: content.to_string(),
+            document: OnceCell::new(),
+            fragment: OnceCell::new(),
         })
     }
 
+    fn validate(rule: &str) -> crate::Result<()>
+    where
+        Self: Sized,
+    {
+        let selector = rule.split_once('@').map(|(s, _)| s).unwrap_or(rule);
+        if !selector.trim().is_empty() {
+            compile_selector(selector)?;
+        }
+        Ok(())
+    }
+
     fn get_elements(&self, rule: &str) -> crate::Result<Vec<String>> {
-        let document = Html::parse_document(&self.content);
-        let selector = Selector::parse(rule.trim()).map_err(|e| anyhow!("{e}"))?;
+        let selector = compile_selector(rule)?;
 
-        Ok(document.select(&selector).map(|el| el.html()).collect())
+        Ok(self
+            .document()
+            .select(&selector)
+            .map(|el| el.html())
+            .collect())
     }
 
     fn get_string(&self, rule: &str) -> crate::Result<String> {
@@ -53,56 +86,61 @@ impl Analyzer for HtmlAnalyzer {
         }
 
         let (selectors, last_rule) = rule.split_once('@').unwrap();
-        let document = Html::parse_document(&self.content);
 
         if selectors.is_empty() {
             return Ok(vec![]);
         }
-        let selector = Selector::parse(selectors).expect("Invalid selector");
+        let selector = compile_selector(selectors)?;
 
-        Ok(document
+        Ok(self
+            .document()
             .select(&selector)
-            .map(|el| self._get_result(last_rule, Some(el.html().as_str())))
+            .map(|el| self._get_result(last_rule, Some(el)))
             .collect())
     }
 }
 
 impl HtmlAnalyzer {
-    fn _get_result(&self, last_rule: &str, html: Option<&str>) -> String {
-        let document = Html::parse_fragment(html.unwrap_or(&self.content));
+    fn document(&self) -> &Html {
+        self.document
+            .get_or_init(|| Html::parse_document(&self.content))
+    }
 
-        match last_rule {
-            "text" => document.root_element().text().collect::<String>(),
-            "textNodes" => {
-                let selector = Selector::parse(":root > *").unwrap();
-                document
-                    .select(&selector)
+    fn fragment(&self) -> &Html {
+        self.fragment
+            .get_or_init(|| Html::parse_fragment(&self.content))
+    }
+
+    fn _get_result(&self, last_rule: &str, element: Option<ElementRef>) -> String {
+        // 没有选中的元素时，以整个片段作为结果
+        let Some(element) = element else {
+            let root = self.fragment().root_element();
+            return match last_rule {
+                "text" => root.text().collect::<String>(),
+                "textNodes" => root
+                    .child_elements()
                     .map(|el| el.text().collect::<String>())
                     .collect::<Vec<String>>()
                     .join("\n")
                     .trim()
-                    .to_string()
-            }
-            "outerHtml" => document.html(),
-            "innerHtml" => {
-                let selector = Selector::parse(":root").unwrap();
-                document
-                    .select(&selector)
-                    .map(|el| el.inner_html())
-                    .collect::<Vec<String>>()
-                    .join("\n")
-                    .trim()
-                    .to_string()
-            }
-            "html" => get_html_string(document.html().as_str()),
-            _ => document
-                .root_element()
-                .child_elements()
-                .next()
-                .unwrap()
-                .attr(last_rule)
-                .unwrap_or("")
-                .to_string(),
+                    .to_string(),
+                "outerHtml" | "innerHtml" => root.inner_html(),
+                "html" => get_html_string(&root.inner_html()),
+                _ => root
+                    .child_elements()
+                    .next()
+                    .and_then(|el| el.attr(last_rule))
+                    .unwrap_or("")
+                    .to_string(),
+            };
+        };
+
+        match last_rule {
+            "text" => element.text().collect::<String>(),
+            "textNodes" => element.text().collect::<String>().trim().to_string(),
+            "outerHtml" | "innerHtml" => element.html(),
+            "html" => get_html_string(&element.html()),
+            _ => element.attr(last_rule).unwrap_or("").to_string(),
         }
     }
 }
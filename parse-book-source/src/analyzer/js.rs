@@ -0,0 +1,287 @@
+//! `<js>` / `@js:` 规则，需要开启 `js` feature
+//!
+//! 脚本中可以使用以下全局变量：
+//! - `result` 上一步规则的结果
+//! - `baseUrl` 书源地址
+//! - `key`、`page` 等额外参数（搜索、发现时传入），`key` 为没有编码的原始关键字
+//! - `java.get(key)` / `java.put(key, value)` 读写 `@get` / `@put` 变量
+//! - `java.encodeURI`、`java.base64Encode`、`java.base64Decode` 等编码函数
+
+use crate::Result;
+#[cfg(not(feature = "js"))]
+use anyhow::anyhow;
+use regex::Regex;
+use serde_json::Value;
+use std::{collections::HashMap, sync::LazyLock};
+
+static JS_RULE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?s)<js>(.*?)</js>|@js:(.*)$").unwrap());
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RulePart {
+    Rule(String),
+    Js(String),
+}
+
+/// 将规则拆分成普通规则和脚本，没有脚本时返回 None
+pub fn split_js(rule: &str) -> Option<Vec<RulePart>> {
+    if !JS_RULE.is_match(rule) {
+        return None;
+    }
+
+    let mut parts = vec![];
+    let mut last = 0;
+    for captures in JS_RULE.captures_iter(rule) {
+        let m = captures.get(0).unwrap();
+        let before = rule[last..m.start()].trim();
+        if !before.is_empty() {
+            parts.push(RulePart::Rule(before.to_string()));
+        }
+        let code = captures
+            .get(1)
+            .or(captures.get(2))
+            .map(|c| c.as_str())
+            .unwrap_or("");
+        parts.push(RulePart::Js(code.to_string()));
+        last = m.end();
+    }
+
+    let rest = rule[last..].trim();
+    if !rest.is_empty() {
+        parts.push(RulePart::Rule(rest.to_string()));
+    }
+    Some(parts)
+}
+
+/// 把脚本的返回值转换成字符串，字符串原样返回，其他值序列化为 JSON
+pub fn js_value_to_string(value: &Value) -> String {
+    match value {
+        Value::Null => "".to_string(),
+        Value::String(s) => s.to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// 单个循环最多执行的次数，避免书源中的死循环卡住程序
+#[cfg(feature = "js")]
+const LOOP_ITERATION_LIMIT: u64 = 200_000;
+
+/// 函数最多递归的层数
+#[cfg(feature = "js")]
+const RECURSION_LIMIT: usize = 256;
+
+#[cfg(feature = "js")]
+const PRELUDE: &str = r#"
+var java = {
+    get: function (key) { return __vars[key]; },
+    put: function (key, value) { __vars[key] = String(value); return value; },
+    encodeURI: function (s, charset) { return __encode(charset ? "url-" + charset : "url", String(s)); },
+    decodeURI: function (s, charset) { return __decode(charset ? "url-" + charset : "url", String(s)); },
+    base64Encode: function (s) { return __encode("base64", String(s)); },
+    base64Decode: function (s) { return __decode("base64", String(s)); },
+    hexEncode: function (s) { return __encode("hex", String(s)); },
+    hexDecode: function (s) { return __decode("hex", String(s)); },
+    htmlEncode: function (s) { return __encode("html-entity", String(s)); },
+    htmlDecode: function (s) { return __decode("html-entity", String(s)); },
+};
+"#;
+
+#[cfg(feature = "js")]
+fn codec_fn(
+    args: &[boa_engine::JsValue],
+    context: &mut boa_engine::Context,
+    f: fn(&str, &str) -> Result<String>,
+) -> boa_engine::JsResult<boa_engine::JsValue> {
+    use boa_engine::{JsError, JsString, JsValue};
+
+    let mut strings = vec![];
+    for arg in args.iter().take(2) {
+        strings.push(arg.to_string(context)?.to_std_string_escaped());
+    }
+    let (Some(mode), Some(content)) = (strings.first(), strings.get(1)) else {
+        return Err(JsError::from_opaque(JsValue::from(JsString::from(
+            "expected (mode, content)",
+        ))));
+    };
+
+    f(mode, content)
+        .map(|s| JsValue::from(JsString::from(s.as_str())))
+        .map_err(|e| JsError::from_opaque(JsValue::from(JsString::from(e.to_string().as_str()))))
+}
+
+/// 执行脚本，返回脚本最后一个表达式的值
+#[cfg(feature = "js")]
+pub fn eval_js(
+    code: &str,
+    result: Value,
+    base_url: &str,
+    extra: Option<&Value>,
+    variables: &mut HashMap<String, String>,
+) -> Result<Value> {
+    use super::codec::{decode, encode};
+    use anyhow::anyhow;
+    use boa_engine::{property::Attribute, Context, JsString, JsValue, NativeFunction, Source};
+
+    let mut context = Context::default();
+    context
+        .runtime_limits_mut()
+        .set_loop_iteration_limit(LOOP_ITERATION_LIMIT);
+    context
+        .runtime_limits_mut()
+        .set_recursion_limit(RECURSION_LIMIT);
+    let js_error = |e: boa_engine::JsError| anyhow!("js error: {}", e);
+
+    let mut globals = serde_json::Map::new();
+    if let Some(Value::Object(extra)) = extra {
+        globals.extend(extra.clone());
+        // 脚本中的 key 是原始关键字，需要编码时使用 java.encodeURI(key)
+        if let Some(raw_key) = extra.get("rawKey") {
+            globals.insert("key".into(), raw_key.clone());
+        }
+    }
+    globals.insert("result".into(), result);
+    globals.insert("baseUrl".into(), Value::String(base_url.to_string()));
+    globals.insert("__vars".into(), serde_json::to_value(&*variables)?);
+
+    for (key, value) in globals {
+        let value = JsValue::from_json(&value, &mut context).map_err(js_error)?;
+        context
+            .register_global_property(JsString::from(key.as_str()), value, Attribute::all())
+            .map_err(js_error)?;
+    }
+
+    context
+        .register_global_callable(
+            JsString::from("__encode"),
+            2,
+            NativeFunction::from_fn_ptr(|_, args, context| codec_fn(args, context, encode)),
+        )
+        .map_err(js_error)?;
+    context
+        .register_global_callable(
+            JsString::from("__decode"),
+            2,
+            NativeFunction::from_fn_ptr(|_, args, context| codec_fn(args, context, decode)),
+        )
+        .map_err(js_error)?;
+
+    context
+        .eval(Source::from_bytes(PRELUDE))
+        .map_err(js_error)?;
+    let value = context.eval(Source::from_bytes(code)).map_err(js_error)?;
+
+    // 同步脚本中 java.put 的变量
+    let vars = context
+        .eval(Source::from_bytes("__vars"))
+        .and_then(|vars| vars.to_json(&mut context))
+        .map_err(js_error)?;
+    if let Value::Object(vars) = vars {
+        for (key, value) in vars {
+            variables.insert(key, js_value_to_string(&value));
+        }
+    }
+
+    if value.is_undefined() {
+        return Ok(Value::Null);
+    }
+    Ok(value.to_json(&mut context).map_err(js_error)?)
+}
+
+#[cfg(not(feature = "js"))]
+pub fn eval_js(
+    code: &str,
+    result: Value,
+    base_url: &str,
+    extra: Option<&Value>,
+    variables: &mut HashMap<String, String>,
+) -> Result<Value> {
+    let _ = (code, result, base_url, extra, variables);
+    Err(anyhow!("JavaScript rules require the `js` feature of parse-book-source").into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_js() {
+        assert_eq!(split_js("$.name"), None);
+        assert_eq!(
+            split_js("$.id<js>result + 1</js>@css:a@js:result.trim()"),
+            Some(vec![
+                RulePart::Rule("$.id".into()),
+                RulePart::Js("result + 1".into()),
+                RulePart::Rule("@css:a".into()),
+                RulePart::Js("result.trim()".into()),
+            ])
+        );
+        assert_eq!(
+            split_js("@js:\nvar a = 1;\na + 1"),
+            Some(vec![RulePart::Js("\nvar a = 1;\na + 1".into())])
+        );
+    }
+
+    #[cfg(feature = "js")]
+    #[test]
+    fn test_eval_js() {
+        use serde_json::json;
+
+        let mut variables = HashMap::from([("bid".to_string(), "42".to_string())]);
+        let value = eval_js(
+            r#"java.put("chapter", page * 2); baseUrl + "/book/" + java.get("bid") + "?key=" + java.encodeURI(key, "gbk")"#,
+            Value::String("".into()),
+            "https://example.com",
+            Some(&json!({ "key": "斗破", "page": 3 })),
+            &mut variables,
+        )
+        .unwrap();
+
+        assert_eq!(value, json!("https://example.com/book/42?key=%B6%B7%C6%C6"));
+        assert_eq!(variables.get("chapter").unwrap(), "6");
+
+        let value = eval_js(
+            "JSON.parse(result).list.map(function (item) { return item.name; })",
+            json!(r#"{"list":[{"name":"a"},{"name":"b"}]}"#),
+            "",
+            None,
+            &mut variables,
+        )
+        .unwrap();
+        assert_eq!(value, json!(["a", "b"]));
+
+        let value = eval_js(
+            "key",
+            Value::Null,
+            "",
+            Some(&json!({ "key": "%E6%96%97", "rawKey": "斗" })),
+            &mut variables,
+        )
+        .unwrap();
+        assert_eq!(value, json!("斗"));
+    }
+
+    #[cfg(feature = "js")]
+    #[test]
+    fn test_eval_js_limits() {
+        let mut variables = HashMap::new();
+        assert!(eval_js("while (true) {}", Value::Null, "", None, &mut variables).is_err());
+        assert!(eval_js(
+            "function f(n) { return f(n + 1); } f(0)",
+            Value::Null,
+            "",
+            None,
+            &mut variables
+        )
+        .is_err());
+        // 限制之内的循环可以正常执行
+        let value = eval_js(
+            "var s = 0; for (var i = 0; i < 1000; i++) { s += i; } s",
+            Value::Null,
+            "",
+            None,
+            &mut variables,
+        )
+        .unwrap();
+        assert_eq!(value, serde_json::json!(499500));
+    }
+}
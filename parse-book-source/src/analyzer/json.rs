@@ -1,6 +1,6 @@
 use super::Analyzer;
-use crate::utils::json_path;
-use crate::Result;
+use crate::utils::{compile_json_path, json_path};
+use crate::{snippet, Result};
 use serde_json::Value;
 
 pub struct JsonPathAnalyzer {
@@ -17,29 +17,56 @@ impl Analyzer for JsonPathAnalyzer {
         })
     }
 
+    fn validate(rule: &str) -> Result<()>
+    where
+        Self: Sized,
+    {
+        compile_json_path(rule)?;
+        Ok(())
+    }
+
     fn get_string(&self, rule: &str) -> Result<String> {
-        let result = json_path(&self.content, rule)?;
-        if let Value::Array(arr) = result {
-            return value_to_string(&arr[0]);
+        // 没有匹配时为空字符串
+        match json_path(&self.content, rule)? {
+            Value::Array(arr) => match arr.first() {
+                Some(value) => value_to_string(value),
+                None => Ok(String::new()),
+            },
+            Value::Null => Ok(String::new()),
+            result => value_to_string(&result),
         }
-        value_to_string(&result)
     }
 
     fn get_elements(&self, rule: &str) -> Result<Vec<String>> {
-        let result = json_path(&self.content, rule)?;
-        if let Value::Array(arr) = result {
-            if arr.len() == 1 && arr[0].is_array() {
-                Ok(arr[0]
-                    .as_array()
-                    .unwrap()
-                    .iter()
-                    .map(|v| v.to_string())
-                    .collect())
-            } else {
-                Ok(arr.into_iter().map(|v| v.to_string()).collect())
+        Ok(self
+            .get_values(rule)?
+            .iter()
+            .map(|v| v.to_string())
+            .collect())
+    }
+}
+
+impl JsonPathAnalyzer {
+    /// 直接使用已经解析的 JSON 值，避免重复解析
+    pub fn from_value(content: Value) -> Self {
+        Self { content }
+    }
+
+    /// 和 [Analyzer::get_elements] 相同，但返回 JSON 值
+    pub fn get_values(&self, rule: &str) -> Result<Vec<Value>> {
+        Self::values(&self.content, rule)
+    }
+
+    /// 对已经解析的 JSON 值执行 [JsonPathAnalyzer::get_values]
+    pub fn values(content: &Value, rule: &str) -> Result<Vec<Value>> {
+        match json_path(content, rule)? {
+            Value::Array(arr) => {
+                if let [Value::Array(list)] = arr.as_slice() {
+                    return Ok(list.clone());
+                }
+                Ok(arr)
             }
-        } else {
-            Ok(vec![result.to_string()])
+            other => Ok(vec![other]),
         }
     }
 }
@@ -49,6 +76,25 @@ pub fn value_to_string(data: &Value) -> crate::Result<String> {
         Value::String(s) => Ok(s.to_string()),
         Value::Number(n) => Ok(n.to_string()),
         Value::Bool(b) => Ok(b.to_string()),
-        _ => Err(anyhow::anyhow!("Invalid value type").into()),
+        other => Err(anyhow::anyhow!(
+            "expected a string, number or bool, found {}",
+            snippet(&other.to_string())
+        )
+        .into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_get_string() {
+        let analyzer = JsonPathAnalyzer::parse(r#"{"list":[],"name":"a","info":{"b":1}}"#).unwrap();
+        assert_eq!(analyzer.get_string("$.name").unwrap(), "a");
+        // 没有匹配时不会 panic
+        assert_eq!(analyzer.get_string("$.list[*]").unwrap(), "");
+        assert_eq!(analyzer.get_string("$.missing").unwrap(), "");
+        assert!(analyzer.get_string("$.info").is_err());
     }
 }
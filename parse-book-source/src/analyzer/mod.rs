@@ -1,30 +1,116 @@
-use crate::Result;
-use regex::Regex;
-use std::fmt::Debug;
+use crate::{utils::compile_regex, Result};
+use ::regex::Regex;
+use anyhow::anyhow;
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    fmt::{self, Debug, Display},
+    rc::Rc,
+};
 pub mod analyzer_manager;
+pub mod codec;
 pub mod default;
 pub mod html;
+pub mod js;
 pub mod json;
-pub use analyzer_manager::AnalyzerManager;
+pub mod regex;
+pub mod value;
+pub mod xpath;
+pub use analyzer_manager::{AnalyzerManager, RuleStep};
+pub use codec::{DecodeAnalyzer, EncodeAnalyzer};
 pub use default::DefaultAnalyzer;
 pub use html::HtmlAnalyzer;
 pub use json::JsonPathAnalyzer;
+pub use regex::RegexAnalyzer;
+pub use value::RuleValue;
+pub use xpath::XPathAnalyzer;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum AnalyzerType {
     JsonPath,
     Html,
+    XPath,
+    Regex,
+    Encode,
+    Decode,
+    Http,
     Default,
 }
 
+/// 每个线程缓存的解析结果数量
+const PARSED_CAPACITY: usize = 8;
+
+/// 解析器类型、内容和解析结果
+type Parsed = (AnalyzerType, String, Rc<dyn Analyzer>);
+
+thread_local! {
+    /// 最近解析过的内容，同一个页面或列表项的多个字段共用解析结果，不用每个字段重新解析
+    /// HTML 解析结果不能跨线程使用，所以按线程缓存
+    static PARSED: RefCell<VecDeque<Parsed>> = RefCell::new(VecDeque::new());
+}
+
 impl AnalyzerType {
+    /// 和 [AnalyzerType::parse_to_analyzer] 相同，HTML、XPath 和 JSON 的解析结果会被缓存
+    pub fn parse_cached(&self, data: &str) -> Result<Rc<dyn Analyzer>> {
+        if !matches!(
+            self,
+            AnalyzerType::Html | AnalyzerType::XPath | AnalyzerType::JsonPath
+        ) {
+            return Ok(Rc::from(self.parse_to_analyzer(data)?));
+        }
+
+        let cached = PARSED.with_borrow(|parsed| {
+            parsed
+                .iter()
+                .find(|(analyzer_type, content, _)| analyzer_type == self && content == data)
+                .map(|(_, _, analyzer)| analyzer.clone())
+        });
+        if let Some(analyzer) = cached {
+            return Ok(analyzer);
+        }
+
+        let analyzer: Rc<dyn Analyzer> = Rc::from(self.parse_to_analyzer(data)?);
+        PARSED.with_borrow_mut(|parsed| {
+            if parsed.len() >= PARSED_CAPACITY {
+                parsed.pop_front();
+            }
+            parsed.push_back((self.clone(), data.to_string(), analyzer.clone()));
+        });
+        Ok(analyzer)
+    }
+
     pub fn parse_to_analyzer(&self, date: &str) -> Result<Box<dyn Analyzer>> {
         match self {
             AnalyzerType::JsonPath => Ok(Box::new(JsonPathAnalyzer::parse(date)?)),
             AnalyzerType::Html => Ok(Box::new(HtmlAnalyzer::parse(date)?)),
+            AnalyzerType::XPath => Ok(Box::new(XPathAnalyzer::parse(date)?)),
+            AnalyzerType::Regex => Ok(Box::new(RegexAnalyzer::parse(date)?)),
+            AnalyzerType::Encode => Ok(Box::new(EncodeAnalyzer::parse(date)?)),
+            AnalyzerType::Decode => Ok(Box::new(DecodeAnalyzer::parse(date)?)),
+            AnalyzerType::Http => Err(anyhow!(
+                "@http: rule must be resolved by AnalyzerManager::get_string_async"
+            )
+            .into()),
             AnalyzerType::Default => Ok(Box::new(DefaultAnalyzer::parse(date)?)),
         }
     }
+
+    /// 检查规则是否有效并缓存编译结果，`&&` / `||` 组合的规则会分别检查
+    pub fn validate(&self, rule: &str) -> Result<()> {
+        for rule in rule.split("&&").flat_map(|rule| rule.split("||")) {
+            match self {
+                AnalyzerType::JsonPath => JsonPathAnalyzer::validate(rule)?,
+                AnalyzerType::Html => HtmlAnalyzer::validate(rule)?,
+                AnalyzerType::XPath => XPathAnalyzer::validate(rule)?,
+                AnalyzerType::Regex => RegexAnalyzer::validate(rule)?,
+                AnalyzerType::Encode => EncodeAnalyzer::validate(rule)?,
+                AnalyzerType::Decode => DecodeAnalyzer::validate(rule)?,
+                AnalyzerType::Http => {}
+                AnalyzerType::Default => DefaultAnalyzer::validate(rule)?,
+            }
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -50,29 +136,56 @@ pub struct SingleRule {
     // 替换内容(## 后面的内容)
     pub replace: String,
     pub analyzer: AnalyzerType,
+    // 编译后的替换正则和替换内容
+    replace_regex: Option<(Regex, String)>,
 }
 
 impl SingleRule {
     pub fn new(rule: &str, replace: Option<&str>, analyzer: AnalyzerType) -> Result<Self> {
+        let replace = replace.unwrap_or("").to_string();
+        let replace_regex = if replace.is_empty() {
+            None
+        } else {
+            let (regex, replace_content) = replace.split_once("##").unwrap_or((&replace, ""));
+            Some((compile_regex(regex)?, replace_content.to_string()))
+        };
+
         Ok(Self {
             rule: rule.to_string(),
-            replace: replace.unwrap_or("").to_string(),
+            replace,
             analyzer,
+            replace_regex,
         })
     }
 
     pub fn replace_content(&self, content: &str) -> Result<String> {
-        if self.replace.is_empty() {
-            return Ok(content.to_string());
+        match &self.replace_regex {
+            Some((regex, replace_content)) => Ok(regex
+                .replace_all(content, replace_content.as_str())
+                .to_string()),
+            None => Ok(content.to_string()),
         }
+    }
+}
 
-        if let Some((regex, replace_content)) = self.replace.split_once("##") {
-            let regex = Regex::new(regex)?;
-            Ok(regex.replace_all(content, replace_content).to_string())
-        } else {
-            let regex = Regex::new(&self.replace)?;
-            Ok(regex.replace_all(content, "").to_string())
+impl Display for SingleRule {
+    /// 还原成规则原文，用于错误信息
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let prefix = match self.analyzer {
+            AnalyzerType::JsonPath if !self.rule.starts_with('$') => "@json:",
+            AnalyzerType::Html => "@css:",
+            AnalyzerType::XPath if !self.rule.starts_with("//") => "@xpath:",
+            AnalyzerType::Regex => "@regex:",
+            AnalyzerType::Encode => "@encode:",
+            AnalyzerType::Decode => "@decode:",
+            AnalyzerType::Http => "@http:",
+            _ => "",
+        };
+        write!(f, "{}{}", prefix, self.rule)?;
+        if !self.replace.is_empty() {
+            write!(f, "##{}", self.replace)?;
         }
+        Ok(())
     }
 }
 
@@ -81,6 +194,15 @@ pub trait Analyzer {
     where
         Self: Sized;
 
+    /// 检查规则是否有效，书源加载时调用，可以在这里缓存编译结果
+    fn validate(rule: &str) -> Result<()>
+    where
+        Self: Sized,
+    {
+        let _ = rule;
+        Ok(())
+    }
+
     fn get_string(&self, rule: &str) -> Result<String> {
         let _ = rule;
         unimplemented!()
@@ -96,3 +218,23 @@ pub trait Analyzer {
         unimplemented!()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cached() {
+        let html = "<p>1</p>";
+        let first = AnalyzerType::Html.parse_cached(html).unwrap();
+        let second = AnalyzerType::Html.parse_cached(html).unwrap();
+        // 同一个内容只解析一次
+        assert!(Rc::ptr_eq(&first, &second));
+        assert_eq!(second.get_string("p@text").unwrap(), "1");
+
+        let other = AnalyzerType::Html.parse_cached("<p>2</p>").unwrap();
+        assert!(!Rc::ptr_eq(&first, &other));
+        let json = AnalyzerType::JsonPath.parse_cached(html);
+        assert!(json.is_err() || !Rc::ptr_eq(&first, &json.unwrap()));
+    }
+}
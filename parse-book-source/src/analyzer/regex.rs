@@ -0,0 +1,115 @@
+use super::Analyzer;
+use crate::{utils::compile_regex, Result};
+use regex::Captures;
+
+/// 正则解析器，对应 `@regex:` / `@regexp:` / `@match:` 规则
+/// 规则格式为 `正则表达式` 或 `正则表达式@@模板`，模板支持 `$1`、`${name}` 等捕获组引用，
+/// 没有模板时取整个匹配内容
+pub struct RegexAnalyzer {
+    content: String,
+}
+
+fn split_template(rule: &str) -> (&str, Option<&str>) {
+    match rule.split_once("@@") {
+        Some((pattern, template)) => (pattern, Some(template)),
+        None => (rule, None),
+    }
+}
+
+fn expand(caps: &Captures, template: Option<&str>) -> String {
+    match template {
+        Some(template) => {
+            let mut res = String::new();
+            caps.expand(template, &mut res);
+            res
+        }
+        None => caps.get(0).map(|m| m.as_str()).unwrap_or("").to_string(),
+    }
+}
+
+impl Analyzer for RegexAnalyzer {
+    fn parse(content: &str) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self {
+            content: content.to_string(),
+        })
+    }
+
+    fn validate(rule: &str) -> Result<()>
+    where
+        Self: Sized,
+    {
+        compile_regex(split_template(rule).0)?;
+        Ok(())
+    }
+
+    /// 只取第一个匹配
+    fn get_string(&self, rule: &str) -> Result<String> {
+        let (pattern, template) = split_template(rule);
+        let regex = compile_regex(pattern)?;
+
+        Ok(regex
+            .captures(&self.content)
+            .map(|caps| expand(&caps, template))
+            .unwrap_or_default())
+    }
+
+    fn get_string_list(&self, rule: &str) -> Result<Vec<String>> {
+        self.get_elements(rule)
+    }
+
+    fn get_elements(&self, rule: &str) -> Result<Vec<String>> {
+        let (pattern, template) = split_template(rule);
+        let regex = compile_regex(pattern)?;
+
+        Ok(regex
+            .captures_iter(&self.content)
+            .map(|caps| expand(&caps, template))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCRIPT: &str = r#"<script>
+var chapters = [{"id":101,"title":"第一章"},{"id":102,"title":"第二章"}];
+var bookId = "42";
+</script>"#;
+
+    #[test]
+    fn test_regex_get_elements() {
+        let analyzer = RegexAnalyzer::parse(SCRIPT).unwrap();
+        let list = analyzer
+            .get_elements(r#"\{"id":\d+,"title":"[^"]+"\}"#)
+            .unwrap();
+        assert_eq!(
+            list,
+            vec![
+                r#"{"id":101,"title":"第一章"}"#,
+                r#"{"id":102,"title":"第二章"}"#
+            ]
+        );
+
+        let list = analyzer
+            .get_elements(r#""id":(\d+),"title":"(?<title>[^"]+)"@@${title}-$1"#)
+            .unwrap();
+        assert_eq!(list, vec!["第一章-101", "第二章-102"]);
+    }
+
+    #[test]
+    fn test_regex_get_string() {
+        let analyzer = RegexAnalyzer::parse(SCRIPT).unwrap();
+        assert_eq!(
+            analyzer
+                .get_string(r#"bookId = "(\d+)"@@/book/$1/"#)
+                .unwrap(),
+            "/book/42/"
+        );
+        assert_eq!(analyzer.get_string(r"var \w+").unwrap(), "var chapters");
+        assert_eq!(analyzer.get_string(r"not found").unwrap(), "");
+    }
+}
@@ -0,0 +1,126 @@
+use serde_json::Value;
+
+/// 规则步骤之间传递的值
+/// 列表规则的每一步都会对上一步结果中的每一项分别执行，再把结果合并成新的列表
+#[derive(Debug, Clone, PartialEq)]
+pub enum RuleValue {
+    /// 原始内容，例如请求到的页面
+    Text(String),
+    /// 字符串列表，HTML / XPath 元素的 outerHtml、正则匹配结果等
+    ///
+    /// HTML 元素仍然以 outerHtml 传递，下一步需要重新解析：scraper 的文档不能跨线程，
+    /// 无法在异步规则之间保存。同一个元素的多个字段通过 [AnalyzerType::parse_cached] 共用解析结果
+    ///
+    /// [AnalyzerType::parse_cached]: super::AnalyzerType::parse_cached
+    Strings(Vec<String>),
+    /// JSON 值列表，下一步是 JSONPath 规则时不需要重新解析
+    Json(Vec<Value>),
+}
+
+impl RuleValue {
+    pub fn len(&self) -> usize {
+        match self {
+            RuleValue::Text(_) => 1,
+            RuleValue::Strings(list) => list.len(),
+            RuleValue::Json(list) => list.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 转换成字符串列表，JSON 值会被序列化，以便后续规则按 JSON 解析
+    pub fn into_strings(self) -> Vec<String> {
+        match self {
+            RuleValue::Text(text) => vec![text],
+            RuleValue::Strings(list) => list,
+            RuleValue::Json(list) => list.iter().map(|v| v.to_string()).collect(),
+        }
+    }
+
+    /// 转换成文本列表，和 [RuleValue::into_strings] 不同的是 JSON 字符串取原值，用于链接等场景
+    pub fn into_texts(self) -> Vec<String> {
+        match self {
+            RuleValue::Json(list) => list
+                .into_iter()
+                .map(|v| match v {
+                    Value::String(s) => s,
+                    other => other.to_string(),
+                })
+                .collect(),
+            other => other.into_strings(),
+        }
+    }
+
+    /// 转换成 JSON 值列表，字符串按 JSON 解析
+    pub fn into_json(self) -> crate::Result<Vec<Value>> {
+        match self {
+            RuleValue::Json(list) => Ok(list),
+            other => other
+                .into_strings()
+                .iter()
+                .map(|s| Ok(serde_json::from_str(s)?))
+                .collect(),
+        }
+    }
+
+    /// 作为脚本中的 result，原始内容为字符串，列表总是数组，即使只有一项
+    pub fn into_js_result(self) -> Value {
+        match self {
+            RuleValue::Text(text) => Value::String(text),
+            RuleValue::Strings(list) => Value::Array(list.into_iter().map(Value::String).collect()),
+            RuleValue::Json(list) => Value::Array(list),
+        }
+    }
+
+    /// 脚本的返回值，包含对象或数组时作为 JSON 列表，否则作为字符串列表
+    pub fn from_js_result(value: Value) -> Self {
+        let list = match value {
+            Value::Null => vec![],
+            Value::Array(list) => list,
+            other => vec![other],
+        };
+        if list.iter().any(|v| v.is_object() || v.is_array()) {
+            RuleValue::Json(list)
+        } else {
+            RuleValue::Strings(list.iter().map(super::js::js_value_to_string).collect())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_rule_value() {
+        let value = RuleValue::Json(vec![json!({"a": 1}), json!("/1.html")]);
+        assert_eq!(
+            value.clone().into_strings(),
+            vec![r#"{"a":1}"#, r#""/1.html""#]
+        );
+        assert_eq!(value.clone().into_texts(), vec![r#"{"a":1}"#, "/1.html"]);
+        assert_eq!(value.into_js_result(), json!([{"a": 1}, "/1.html"]));
+        assert_eq!(
+            RuleValue::Strings(vec!["<a>1</a>".into()]).into_js_result(),
+            json!(["<a>1</a>"])
+        );
+        assert_eq!(
+            RuleValue::Json(vec![json!({"a": 1})]).into_js_result(),
+            json!([{"a": 1}])
+        );
+        assert_eq!(RuleValue::Text("a".into()).into_js_result(), json!("a"));
+
+        assert_eq!(
+            RuleValue::from_js_result(json!(["a", 1])),
+            RuleValue::Strings(vec!["a".into(), "1".into()])
+        );
+        assert_eq!(
+            RuleValue::from_js_result(json!([{"a": 1}])),
+            RuleValue::Json(vec![json!({"a": 1})])
+        );
+        assert!(RuleValue::from_js_result(Value::Null).is_empty());
+    }
+}
@@ -0,0 +1,259 @@
+use super::Analyzer;
+use crate::Result;
+use anyhow::anyhow;
+use scraper::{node::Node as HtmlNode, ElementRef, Html};
+use sxd_document::{
+    dom::{Document, Element},
+    Package,
+};
+use sxd_xpath::{nodeset::Node, Context, Factory, Value};
+
+/// XPath 解析器
+/// 支持 HTML 和 XML，HTML 先用 scraper 容错解析，再转换成 sxd 文档用于 XPath 求值
+pub struct XPathAnalyzer {
+    package: Package,
+    // 是否是片段（上一步规则的结果），片段时以唯一的顶层元素作为上下文节点
+    fragment: bool,
+}
+
+fn is_document(content: &str) -> bool {
+    let head = content
+        .trim_start()
+        .chars()
+        .take(256)
+        .collect::<String>()
+        .to_lowercase();
+    head.starts_with("<!doctype") || head.starts_with("<html") || head.contains("<head")
+}
+
+fn build_element<'d>(document: &Document<'d>, node: ElementRef) -> Element<'d> {
+    let element = document.create_element(node.value().name());
+    for (name, value) in node.value().attrs() {
+        element.set_attribute_value(name, value);
+    }
+
+    for child in node.children() {
+        match child.value() {
+            HtmlNode::Element(_) => {
+                if let Some(child) = ElementRef::wrap(child) {
+                    element.append_child(build_element(document, child));
+                }
+            }
+            HtmlNode::Text(text) => element.append_child(document.create_text(text)),
+            HtmlNode::Comment(comment) => element.append_child(document.create_comment(comment)),
+            _ => {}
+        }
+    }
+    element
+}
+
+fn html_escape(s: &str, attr: bool) -> String {
+    let s = s
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;");
+    if attr {
+        s.replace('"', "&quot;")
+    } else {
+        s
+    }
+}
+
+fn element_to_html(element: Element) -> String {
+    let name = element.name().local_part().to_string();
+    let mut html = format!("<{}", name);
+    for attr in element.attributes() {
+        html.push_str(&format!(
+            r#" {}="{}""#,
+            attr.name().local_part(),
+            html_escape(attr.value(), true)
+        ));
+    }
+    html.push('>');
+
+    for child in element.children() {
+        if let Some(el) = child.element() {
+            html.push_str(&element_to_html(el));
+        } else if let Some(text) = child.text() {
+            html.push_str(&html_escape(text.text(), false));
+        }
+    }
+    html.push_str(&format!("</{}>", name));
+    html
+}
+
+fn node_to_string(node: Node) -> String {
+    match node {
+        Node::Element(el) => element_to_html(el),
+        Node::Root(root) => root
+            .children()
+            .into_iter()
+            .filter_map(|child| child.element())
+            .map(element_to_html)
+            .collect(),
+        other => other.string_value(),
+    }
+}
+
+impl XPathAnalyzer {
+    fn evaluate<T>(&self, rule: &str, f: impl FnOnce(Value) -> T) -> Result<T> {
+        let document = self.package.as_document();
+        let xpath = Factory::new()
+            .build(rule.trim())
+            .map_err(|e| anyhow!("Invalid xpath {}: {}", rule, e))?
+            .ok_or(anyhow!("xpath is empty"))?;
+
+        let root = document.root();
+        let context_node: Node = if self.fragment {
+            // 片段被包裹在 html 元素中，只有一个顶层元素时以它作为上下文
+            match root.children().first().and_then(|child| child.element()) {
+                Some(wrapper) => {
+                    let elements = wrapper
+                        .children()
+                        .into_iter()
+                        .filter_map(|child| child.element())
+                        .collect::<Vec<_>>();
+                    if elements.len() == 1 {
+                        elements[0].into()
+                    } else {
+                        wrapper.into()
+                    }
+                }
+                None => root.into(),
+            }
+        } else {
+            root.into()
+        };
+
+        let value = xpath
+            .evaluate(&Context::new(), context_node)
+            .map_err(|e| anyhow!("xpath evaluate error {}: {}", rule, e))?;
+        Ok(f(value))
+    }
+}
+
+impl Analyzer for XPathAnalyzer {
+    fn parse(content: &str) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        if content.trim_start().starts_with("<?xml") {
+            if let Ok(package) = sxd_document::parser::parse(content) {
+                return Ok(Self {
+                    package,
+                    fragment: false,
+                });
+            }
+        }
+
+        let fragment = !is_document(content);
+        let html = if fragment {
+            Html::parse_fragment(content)
+        } else {
+            Html::parse_document(content)
+        };
+
+        let package = Package::new();
+        {
+            let document = package.as_document();
+            let root_element = build_element(&document, html.root_element());
+            document.root().append_child(root_element);
+        }
+
+        Ok(Self { package, fragment })
+    }
+
+    fn validate(rule: &str) -> Result<()>
+    where
+        Self: Sized,
+    {
+        Factory::new()
+            .build(rule.trim())
+            .map_err(|e| anyhow!("Invalid xpath {}: {}", rule, e))?;
+        Ok(())
+    }
+
+    fn get_string(&self, rule: &str) -> Result<String> {
+        Ok(self.get_string_list(rule)?.join("\n"))
+    }
+
+    fn get_string_list(&self, rule: &str) -> Result<Vec<String>> {
+        self.evaluate(rule, |value| match value {
+            Value::Nodeset(nodes) => nodes
+                .document_order()
+                .into_iter()
+                .map(|node| node.string_value().trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect(),
+            other => vec![other.into_string()],
+        })
+    }
+
+    fn get_elements(&self, rule: &str) -> Result<Vec<String>> {
+        self.evaluate(rule, |value| match value {
+            Value::Nodeset(nodes) => nodes
+                .document_order()
+                .into_iter()
+                .map(node_to_string)
+                .collect(),
+            other => vec![other.into_string()],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HTML: &str = r#"<!DOCTYPE html>
+<html>
+<head><title>书名</title></head>
+<body>
+  <ul class="list">
+    <li><a href="/1.html">第一章</a></li>
+    <li><a href="/2.html">第二章</a></li>
+  </ul>
+  <div id="content">第一段<br>第二段</div>
+</body>
+</html>"#;
+
+    #[test]
+    fn test_xpath_get_elements() {
+        let analyzer = XPathAnalyzer::parse(HTML).unwrap();
+        let list = analyzer.get_elements("//ul[@class='list']/li").unwrap();
+        assert_eq!(list.len(), 2);
+        assert_eq!(list[0], r#"<li><a href="/1.html">第一章</a></li>"#);
+
+        let item = XPathAnalyzer::parse(&list[1]).unwrap();
+        assert_eq!(item.get_string("./a/@href").unwrap(), "/2.html");
+        assert_eq!(item.get_string("//a/text()").unwrap(), "第二章");
+    }
+
+    #[test]
+    fn test_xpath_get_string() {
+        let analyzer = XPathAnalyzer::parse(HTML).unwrap();
+        assert_eq!(analyzer.get_string("//title/text()").unwrap(), "书名");
+        assert_eq!(
+            analyzer.get_string("//div[@id='content']/text()").unwrap(),
+            "第一段\n第二段"
+        );
+        assert_eq!(analyzer.get_string("count(//li)").unwrap(), "2");
+        assert_eq!(
+            analyzer.get_string_list("//li/a/@href").unwrap(),
+            vec!["/1.html", "/2.html"]
+        );
+    }
+
+    #[test]
+    fn test_xpath_xml() {
+        let analyzer = XPathAnalyzer::parse(
+            r#"<?xml version="1.0" encoding="UTF-8"?><books><book id="1"><name>A</name></book><book id="2"><name>B</name></book></books>"#,
+        )
+        .unwrap();
+        assert_eq!(
+            analyzer.get_string_list("/books/book/name").unwrap(),
+            vec!["A", "B"]
+        );
+        assert_eq!(analyzer.get_string("//book[2]/@id").unwrap(), "2");
+    }
+}
@@ -0,0 +1,185 @@
+//! 书籍列表的筛选条件
+//!
+//! 条件之间用空白分隔，需要全部满足，不区分大小写：
+//!
+//! - `玄幻`：名称、作者、类型、状态、标签、最新章节或其他信息中包含
+//! - `状态:完结`、`tag:系统`：指定字段包含，见 [BookFilter::parse]
+//! - `评分>=8.5`：评分满足比较条件，没有评分的书不满足
+
+use super::BookInfo;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Compare {
+    Ge,
+    Le,
+    Gt,
+    Lt,
+    Eq,
+}
+
+impl Compare {
+    fn test(self, left: f64, right: f64) -> bool {
+        match self {
+            Compare::Ge => left >= right,
+            Compare::Le => left <= right,
+            Compare::Gt => left > right,
+            Compare::Lt => left < right,
+            Compare::Eq => (left - right).abs() < f64::EPSILON,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum FilterTerm {
+    Text(String),
+    Field { key: String, value: String },
+    Rating { compare: Compare, value: f64 },
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BookFilter {
+    terms: Vec<FilterTerm>,
+}
+
+/// 取出文本中的第一个数字，例如 `9.1分` 中的 `9.1`
+pub fn parse_rating(rating: &str) -> Option<f64> {
+    let start = rating.find(|c: char| c.is_ascii_digit())?;
+    let rest = &rating[start..];
+    let end = rest
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(rest.len());
+    rest[..end].trim_end_matches('.').parse().ok()
+}
+
+fn parse_rating_term(term: &str) -> Option<FilterTerm> {
+    let rest = ["rating", "评分"]
+        .iter()
+        .find_map(|key| term.strip_prefix(key))?;
+    let (compare, value) = [
+        (">=", Compare::Ge),
+        ("<=", Compare::Le),
+        (">", Compare::Gt),
+        ("<", Compare::Lt),
+        ("=", Compare::Eq),
+    ]
+    .iter()
+    .find_map(|(op, compare)| rest.strip_prefix(op).map(|value| (*compare, value)))?;
+    Some(FilterTerm::Rating {
+        compare,
+        value: value.parse().ok()?,
+    })
+}
+
+fn parse_term(term: &str) -> FilterTerm {
+    if let Some(rating) = parse_rating_term(term) {
+        return rating;
+    }
+    if let Some((key, value)) = term.split_once([':', '：']) {
+        if !key.is_empty() && !value.is_empty() {
+            return FilterTerm::Field {
+                key: key.to_string(),
+                value: value.to_lowercase(),
+            };
+        }
+    }
+    FilterTerm::Text(term.to_lowercase())
+}
+
+fn contains(text: &str, value: &str) -> bool {
+    text.to_lowercase().contains(value)
+}
+
+impl BookFilter {
+    /// 解析筛选条件，`键:值` 中的键可以是
+    /// `name`/`名称`、`author`/`作者`、`kind`/`类型`、`status`/`状态`、
+    /// `tag`/`标签`，其他的键匹配书源 `extra` 中同名的信息
+    pub fn parse(filter: &str) -> Self {
+        Self {
+            terms: filter.split_whitespace().map(parse_term).collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    pub fn matches(&self, book_info: &BookInfo) -> bool {
+        self.terms.iter().all(|term| match term {
+            FilterTerm::Text(value) => [
+                &book_info.name,
+                &book_info.author,
+                &book_info.kind,
+                &book_info.status,
+                &book_info.last_chapter,
+            ]
+            .into_iter()
+            .chain(&book_info.tags)
+            .chain(book_info.extra.values())
+            .any(|text| contains(text, value)),
+            FilterTerm::Field { key, value } => match key.to_lowercase().as_str() {
+                "name" | "名称" | "书名" => contains(&book_info.name, value),
+                "author" | "作者" => contains(&book_info.author, value),
+                "kind" | "类型" => contains(&book_info.kind, value),
+                "status" | "状态" => contains(&book_info.status, value),
+                "tag" | "tags" | "标签" => book_info.tags.iter().any(|tag| contains(tag, value)),
+                _ => book_info
+                    .extra
+                    .get(key)
+                    .is_some_and(|text| contains(text, value)),
+            },
+            FilterTerm::Rating { compare, value } => {
+                parse_rating(&book_info.rating).is_some_and(|rating| compare.test(rating, *value))
+            }
+        })
+    }
+}
+
+impl FromStr for BookFilter {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Ok(Self::parse(s))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn book() -> BookInfo {
+        BookInfo {
+            name: "诡秘之主".into(),
+            author: "爱潜水的乌贼".into(),
+            kind: "玄幻".into(),
+            status: "已完结".into(),
+            tags: vec!["克苏鲁".into(), "Steampunk".into()],
+            rating: "9.1分".into(),
+            extra: [("平台".to_string(), "起点".to_string())].into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_rating() {
+        assert_eq!(parse_rating("9.1分"), Some(9.1));
+        assert_eq!(parse_rating("评分：8."), Some(8.0));
+        assert_eq!(parse_rating("暂无"), None);
+    }
+
+    #[test]
+    fn test_filter() {
+        let book = book();
+        assert!(BookFilter::parse("").matches(&book));
+        assert!(BookFilter::parse("玄幻 steampunk").matches(&book));
+        assert!(BookFilter::parse("状态:完结 标签：克苏鲁").matches(&book));
+        assert!(BookFilter::parse("平台:起点 评分>=9").matches(&book));
+        assert!(BookFilter::parse("rating<9.5").matches(&book));
+
+        assert!(!BookFilter::parse("玄幻 都市").matches(&book));
+        assert!(!BookFilter::parse("status:连载").matches(&book));
+        assert!(!BookFilter::parse("平台:晋江").matches(&book));
+        assert!(!BookFilter::parse("评分>9.5").matches(&book));
+        assert!(!BookFilter::parse("评分>=1").matches(&BookInfo::default()));
+    }
+}
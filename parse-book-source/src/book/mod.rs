@@ -1,4 +1,9 @@
+use crate::utils::resolve_url;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+pub mod filter;
+pub use filter::*;
 
 pub type BookList = Vec<BookListItem>;
 pub type ChapterList = Vec<Chapter>;
@@ -18,7 +23,7 @@ pub struct BookListItem {
     pub book_info: BookInfo,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct BookInfo {
     pub author: String,
     pub cover_url: String,
@@ -28,10 +33,54 @@ pub struct BookInfo {
     pub name: String,
     pub toc_url: String,
     pub word_count: String,
+    #[serde(default)]
+    pub status: String,
+    #[serde(default)]
+    pub update_time: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub rating: String,
+    /// 书源 `extra` 规则解析出的其他信息
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub extra: BTreeMap<String, String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Chapter {
     pub chapter_name: String,
     pub chapter_url: String,
+    /// 是否是分卷标题，分卷标题没有正文
+    #[serde(default)]
+    pub is_volume: bool,
+    /// 是否是需要付费的章节
+    #[serde(default)]
+    pub is_vip: bool,
+    #[serde(default)]
+    pub update_time: String,
+    #[serde(default)]
+    pub word_count: String,
+}
+
+impl BookListItem {
+    /// 把相对链接解析为相对于 `base` 的绝对链接，见 [resolve_url]
+    pub fn resolve_urls(&mut self, base: &str) {
+        self.book_url = resolve_url(base, &self.book_url);
+        self.book_info.resolve_urls(base);
+    }
+}
+
+impl BookInfo {
+    /// 把相对链接解析为相对于 `base` 的绝对链接，见 [resolve_url]
+    pub fn resolve_urls(&mut self, base: &str) {
+        self.cover_url = resolve_url(base, &self.cover_url);
+        self.toc_url = resolve_url(base, &self.toc_url);
+    }
+}
+
+impl Chapter {
+    /// 把相对链接解析为相对于 `base` 的绝对链接，见 [resolve_url]
+    pub fn resolve_urls(&mut self, base: &str) {
+        self.chapter_url = resolve_url(base, &self.chapter_url);
+    }
 }
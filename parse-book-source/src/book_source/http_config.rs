@@ -1,13 +1,55 @@
+use crate::{Result, Stage};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use serde_json::{Map, Value};
+use std::{collections::HashMap, time::Duration};
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct HttpConfig {
     pub timeout: Option<u64>,
     pub header: Option<HashMap<String, String>>,
-    /// 请求速率限制（令牌桶算法）
+    /// 请求速率限制（令牌桶算法），同一个域名的请求共享令牌桶
     pub rate_limit: Option<RateLimit>,
+    /// 搜索关键字的编码字符集，例如 `gbk`，默认 `utf-8`
+    pub search_charset: Option<String>,
+    /// 响应内容的字符集，例如 `gbk`，不设置时根据响应头、meta 标签等自动检测
+    pub charset: Option<String>,
+    /// 请求失败时的重试策略，不设置时不重试
+    pub retry: Option<RetryPolicy>,
+    /// 代理地址，支持 `http://`、`https://`、`socks5://` 和 `socks5h://`
+    pub proxy: Option<String>,
+    /// 固定域名解析的地址，例如 `{"www.example.com": "1.2.3.4"}`，端口以链接为准
+    pub resolve: Option<HashMap<String, String>>,
+    /// 是否接受无效的证书（自签名、过期等）
+    pub accept_invalid_certs: Option<bool>,
+    /// 额外信任的根证书，PEM 格式的文件路径或者证书内容
+    pub ca_cert: Option<String>,
+    /// 响应缓存的有效期，不设置时使用 [CacheTtl] 的默认值，设置了缓存目录才会缓存，
+    /// 见 [crate::HttpClient::with_cache_dir]
+    pub cache: Option<CacheTtl>,
+    /// 全局最大并发请求数，只在全局配置中生效，见 [crate::rate_limiter::set_max_concurrency]
+    pub max_concurrency: Option<usize>,
+}
+
+impl HttpConfig {
+    /// 使用全局配置补全书源没有设置的网络选项，书源的配置优先
+    pub fn with_defaults(mut self, defaults: &HttpConfig) -> Self {
+        self.timeout = self.timeout.or(defaults.timeout);
+        self.retry = self.retry.or_else(|| defaults.retry.clone());
+        self.rate_limit = self.rate_limit.or_else(|| defaults.rate_limit.clone());
+        self.proxy = self.proxy.or_else(|| defaults.proxy.clone());
+        self.accept_invalid_certs = self.accept_invalid_certs.or(defaults.accept_invalid_certs);
+        self.ca_cert = self.ca_cert.or_else(|| defaults.ca_cert.clone());
+        self.cache = self.cache.or_else(|| defaults.cache.clone());
+
+        if let Some(defaults) = &defaults.resolve {
+            let resolve = self.resolve.get_or_insert_with(HashMap::new);
+            for (host, addr) in defaults {
+                resolve.entry(host.clone()).or_insert_with(|| addr.clone());
+            }
+        }
+        self
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -18,3 +60,196 @@ pub struct RateLimit {
     /// 每隔多少秒补充一次令牌
     pub fill_duration: f64,
 }
+
+/// 重试策略，连接失败、超时和指定的状态码会按指数退避重试，每次重试同样受速率限制
+///
+/// 默认只重试 GET 和 HEAD 请求，POST 等请求重试可能重复提交，需要设置 `nonIdempotent`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RetryPolicy {
+    /// 最多请求次数，包括第一次请求
+    pub max_attempts: u32,
+    /// 第一次重试前等待的毫秒数，之后每次翻倍
+    pub base_delay: u64,
+    /// 最长等待的毫秒数，同样限制 `Retry-After` 响应头
+    pub max_delay: u64,
+    /// 是否在等待时间上增加随机抖动，避免同时重试
+    pub jitter: bool,
+    /// 需要重试的状态码
+    pub status_codes: Vec<u16>,
+    /// 是否重试 POST 等非幂等请求
+    pub non_idempotent: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: 500,
+            max_delay: 10_000,
+            jitter: true,
+            status_codes: vec![408, 429, 500, 502, 503, 504],
+            non_idempotent: false,
+        }
+    }
+}
+
+/// 各个阶段响应缓存的有效期，单位为秒，为空时该阶段不缓存
+///
+/// 有效期内直接使用缓存，不访问网络；过期之后带上 `ETag` 和 `Last-Modified`
+/// 发送条件请求，服务器返回 304 时继续使用缓存
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CacheTtl {
+    pub search: Option<u64>,
+    pub explore: Option<u64>,
+    pub info: Option<u64>,
+    pub toc: Option<u64>,
+    pub content: Option<u64>,
+}
+
+impl Default for CacheTtl {
+    fn default() -> Self {
+        Self {
+            search: None,
+            explore: Some(24 * 60 * 60),
+            info: Some(60 * 60),
+            toc: Some(10 * 60),
+            content: Some(30 * 24 * 60 * 60),
+        }
+    }
+}
+
+impl CacheTtl {
+    /// 阶段对应的有效期，登录请求不缓存
+    pub fn for_stage(&self, stage: Stage) -> Option<Duration> {
+        let ttl = match stage {
+            Stage::Search => self.search,
+            Stage::Explore => self.explore,
+            Stage::Info => self.info,
+            Stage::Toc => self.toc,
+            Stage::Content => self.content,
+            Stage::Login => None,
+        };
+        ttl.map(Duration::from_secs)
+    }
+}
+
+/// 链接后缀的请求选项，例如 `/search,{"method":"POST","body":"q={{key}}&page={{page}}"}`
+/// 可以用于 `searchUrl`、发现链接和目录链接
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestOptions {
+    /// 请求方法，默认 `GET`
+    pub method: Option<String>,
+    /// 请求体，字符串或 JSON 对象，支持 `{{key}}`、`{{page}}` 等表达式
+    pub body: Option<Value>,
+    /// 额外的请求头，会覆盖书源的请求头
+    pub headers: Option<HashMap<String, String>>,
+    /// 关键字和响应内容的字符集，例如 `gbk`
+    pub charset: Option<String>,
+    /// 请求体类型，默认根据请求体推断 `application/json` 或 `application/x-www-form-urlencoded`
+    pub content_type: Option<String>,
+}
+
+/// 解析宽松的 JSON 对象，允许单引号
+pub(crate) fn parse_lenient_object(content: &str) -> Option<Map<String, Value>> {
+    serde_json::from_str(content)
+        .or_else(|_| serde_json::from_str(&content.replace('\'', "\"")))
+        .ok()
+}
+
+/// 拆分链接和后缀的请求选项，没有选项时返回 None
+pub fn split_url_options(url: &str) -> (String, Option<Map<String, Value>>) {
+    for (index, _) in url.match_indices(',') {
+        let rest = url[index + 1..].trim_start();
+        if !rest.starts_with('{') {
+            continue;
+        }
+        if let Some(options) = parse_lenient_object(rest) {
+            return (url[..index].trim().to_string(), Some(options));
+        }
+    }
+    (url.trim().to_string(), None)
+}
+
+impl RequestOptions {
+    /// 拆分链接和请求选项，没有选项时返回默认选项
+    pub fn from_url(url: &str) -> Result<(String, Self)> {
+        let (url, options) = split_url_options(url);
+        let options = match options {
+            Some(options) => serde_json::from_value(Value::Object(options))?,
+            None => Self::default(),
+        };
+        Ok((url, options))
+    }
+
+    /// 请求体是否是 JSON，没有指定类型时根据请求体推断，和发送请求时的判断相同
+    pub fn is_json_body(&self) -> bool {
+        match (&self.content_type, &self.body) {
+            (Some(content_type), _) => content_type.contains("json"),
+            (None, Some(Value::String(body))) => body.trim_start().starts_with(['{', '[']),
+            (None, Some(_)) => true,
+            (None, None) => false,
+        }
+    }
+
+    /// 请求体转换成字符串，JSON 对象会被序列化
+    pub fn body_string(&self) -> Option<String> {
+        self.body.as_ref().map(|body| match body {
+            Value::String(body) => body.clone(),
+            other => other.to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_http_config_with_defaults() {
+        let defaults = HttpConfig {
+            timeout: Some(5000),
+            proxy: Some("socks5://127.0.0.1:1080".into()),
+            resolve: Some(HashMap::from([
+                ("a.com".into(), "1.1.1.1".into()),
+                ("b.com".into(), "2.2.2.2".into()),
+            ])),
+            accept_invalid_certs: Some(true),
+            ..Default::default()
+        };
+        let config = HttpConfig {
+            timeout: Some(1000),
+            resolve: Some(HashMap::from([("a.com".into(), "3.3.3.3".into())])),
+            accept_invalid_certs: Some(false),
+            ..Default::default()
+        }
+        .with_defaults(&defaults);
+
+        assert_eq!(config.timeout, Some(1000));
+        assert_eq!(config.proxy, defaults.proxy);
+        assert_eq!(config.accept_invalid_certs, Some(false));
+        let resolve = config.resolve.unwrap();
+        assert_eq!(resolve["a.com"], "3.3.3.3");
+        assert_eq!(resolve["b.com"], "2.2.2.2");
+    }
+
+    #[test]
+    fn test_request_options_from_url() {
+        let (url, options) = RequestOptions::from_url(
+            "/search?a=1,2,{'method': 'POST', 'body': {'key': '{{key}}'}, 'charset': 'gbk'}",
+        )
+        .unwrap();
+        assert_eq!(url, "/search?a=1,2");
+        assert_eq!(options.method.as_deref(), Some("POST"));
+        assert_eq!(options.charset.as_deref(), Some("gbk"));
+        assert_eq!(options.body, Some(json!({ "key": "{{key}}" })));
+        assert_eq!(options.body_string().unwrap(), r#"{"key":"{{key}}"}"#);
+
+        let (url, options) = RequestOptions::from_url("/list/{{page}}.html").unwrap();
+        assert_eq!(url, "/list/{{page}}.html");
+        assert_eq!(options, RequestOptions::default());
+    }
+}
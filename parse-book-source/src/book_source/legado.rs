@@ -0,0 +1,804 @@
+//! 阅读（Legado）书源转换
+//!
+//! 阅读书源的字段和 TRNovel 大体一致，但有几处需要转换：
+//! - 链接后缀的请求选项 `url,{"webView":true}` 中不支持的字段
+//! - `exploreUrl` 的 `标题::链接` 文本格式和脚本格式
+//! - 列表规则前缀 `-` 表示倒序（只有目录支持）、`@ownText` 等取值方式
+//! - 不区分大小写的规则前缀 `@CSS:`、`@XPath:`、`@Json:`，以及 `text.` 文本选择器
+//! - `header` 是 JSON 字符串，`concurrentRate` 表示请求频率
+//!
+//! 无法转换的字段会记录在 [ImportWarning] 中
+
+use super::{
+    parse_lenient_object, split_url_options, BookSource, ContentCleanup, HttpConfig, ImportWarning,
+    RateLimit, ReplaceRule, RuleBookInfo, RuleContent, RuleExploreItem, RuleSearch, RuleToc,
+};
+use crate::{analyzer::js::split_js, Result};
+use anyhow::anyhow;
+use regex::Regex;
+use serde_json::{Map, Value};
+use std::{collections::HashMap, sync::LazyLock};
+
+/// 阅读书源特有的字段，用于识别书源格式
+const LEGADO_KEYS: [&str; 5] = [
+    "bookSourceType",
+    "enabled",
+    "enabledExplore",
+    "customOrder",
+    "weight",
+];
+
+/// 只用于阅读自身展示或统计，转换时直接忽略
+const IGNORED_KEYS: [&str; 9] = [
+    "enabled",
+    "enabledExplore",
+    "enabledCookieJar",
+    "customOrder",
+    "weight",
+    "bookSourceComment",
+    "variableComment",
+    "respondTime",
+    "bookUrlPattern",
+];
+
+/// 支持的请求选项
+const REQUEST_OPTIONS: [&str; 5] = ["method", "body", "headers", "charset", "contentType"];
+
+/// 脚本中可以使用的 `java.*` 函数，见 [crate::analyzer::js]
+const JAVA_FUNCTIONS: [&str; 10] = [
+    "get",
+    "put",
+    "encodeURI",
+    "decodeURI",
+    "base64Encode",
+    "base64Decode",
+    "hexEncode",
+    "hexDecode",
+    "htmlEncode",
+    "htmlDecode",
+];
+
+static JAVA_CALL: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b(java|source|cookie|cache)\.(\w+)").unwrap());
+static OWN_TEXT: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"@ownText\b").unwrap());
+static RULE_PREFIX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)@(css|json|xpath):").unwrap());
+static JS_REGION: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?s)<js>.*?</js>|@js:.*$").unwrap());
+/// 阅读的文本选择器，选择自身文本包含指定内容的元素，例如 `text.下一页@href`
+static TEXT_SELECTOR: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?:^|@)text\.").unwrap());
+static SIMPLE_TEXT_SELECTOR: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^text\.([^@.\[\]'&|]+)@(\w+)$").unwrap());
+static PAGE_TEMPLATE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"<[^<>]*,[^<>]*>").unwrap());
+
+/// 把 explore 脚本的返回值（JSON 字符串、`标题::链接` 文本或数组）统一成 JSON 对象列表
+const EXPLORE_JS: &str = r#"<js>(function (r) {
+    // 上一个脚本返回的字符串以单项列表传入
+    if (Array.isArray(r) && r.length === 1 && typeof r[0] === "string") {
+        r = r[0];
+    }
+    if (typeof r === "string") {
+        try {
+            r = JSON.parse(r);
+        } catch (e) {
+            r = r.split(/&&|\n/).map(function (line) {
+                var parts = line.split("::");
+                return { title: parts[0], url: parts[1] };
+            });
+        }
+    }
+    return r.filter(function (item) { return item.url; })
+        .map(function (item) { return JSON.stringify({ title: item.title, url: item.url }); });
+})(result)</js>"#;
+
+/// 判断是否是阅读格式的书源
+pub fn is_legado(value: &Value) -> bool {
+    value
+        .as_object()
+        .is_some_and(|object| LEGADO_KEYS.iter().any(|key| object.contains_key(*key)))
+}
+
+/// 把阅读书源转换成 TRNovel 书源，同时返回无法转换的字段
+pub fn from_legado(value: &Value) -> Result<(BookSource, Vec<ImportWarning>)> {
+    let mut source = value
+        .as_object()
+        .cloned()
+        .ok_or(anyhow!("book source is not an object"))?;
+
+    let mut converter = Converter {
+        name: value_to_string(source.get("bookSourceName")),
+        warnings: vec![],
+    };
+
+    if let Some(book_source_type) = source.remove("bookSourceType") {
+        if book_source_type.as_u64().unwrap_or(0) != 0 {
+            return Err(anyhow!("只支持文本书源, bookSourceType: {}", book_source_type).into());
+        }
+    }
+
+    let book_source_name = converter.take_str(&mut source, "", "bookSourceName");
+    let book_source_url = converter.take_str(&mut source, "", "bookSourceUrl");
+    if book_source_url.is_empty() {
+        return Err(anyhow!("bookSourceUrl is empty").into());
+    }
+    let book_source_group = converter.take_str(&mut source, "", "bookSourceGroup");
+    let last_update_time = source
+        .remove("lastUpdateTime")
+        .and_then(|v| v.as_u64())
+        .unwrap_or_default();
+
+    let mut http_config = HttpConfig::default();
+    let header = converter.convert_header(source.remove("header"));
+    http_config.rate_limit = converter.convert_rate(source.remove("concurrentRate"));
+
+    let search_url = converter.take_str(&mut source, "", "searchUrl");
+    let search_url = converter.convert_url("searchUrl", &search_url);
+
+    let (explore_url, rule_explore_item) =
+        converter.convert_explore_url(source.remove("exploreUrl"));
+
+    let rule_search = match converter.take_object(&mut source, "ruleSearch") {
+        Some(mut rule) => converter.convert_rule_search("ruleSearch", &mut rule),
+        None => converter.convert_rule_search("ruleSearch", &mut Map::new()),
+    };
+    // 阅读中发现规则为空时使用搜索规则
+    let rule_explore = match converter.take_object(&mut source, "ruleExplore") {
+        Some(mut rule) if !value_to_string(rule.get("bookList")).is_empty() => {
+            Some(converter.convert_rule_search("ruleExplore", &mut rule))
+        }
+        _ => explore_url.as_ref().map(|_| rule_search.clone()),
+    };
+
+    let mut rule = converter
+        .take_object(&mut source, "ruleBookInfo")
+        .unwrap_or_default();
+    let rule_book_info = converter.convert_rule_book_info("ruleBookInfo", &mut rule);
+    converter.check_rest("ruleBookInfo", rule, &[]);
+
+    let mut rule = converter
+        .take_object(&mut source, "ruleToc")
+        .unwrap_or_default();
+    let rule_toc = converter.convert_rule_toc(&mut rule);
+
+    let mut rule = converter
+        .take_object(&mut source, "ruleContent")
+        .unwrap_or_default();
+    let rule_content = converter.convert_rule_content(&mut rule);
+
+    // 阅读的登录使用脚本和网页，无法直接转换
+    let login = ["loginUrl", "loginUi", "loginCheckJs"]
+        .into_iter()
+        .filter(|key| {
+            source
+                .remove(*key)
+                .is_some_and(|value| !is_empty_value(&value))
+        })
+        .collect::<Vec<_>>();
+    if !login.is_empty() {
+        converter.warn(
+            &login.join(", "),
+            "阅读的登录脚本无法转换，请手动设置 loginUrl 和 loginCheck",
+        );
+    }
+
+    converter.check_rest("", source, &IGNORED_KEYS);
+
+    Ok((
+        BookSource {
+            book_source_group,
+            book_source_name,
+            book_source_url,
+            last_update_time,
+            search_url,
+            explore_url,
+            rule_explore_item,
+            header,
+            respond_time: None,
+            login_url: None,
+            login_check: None,
+            http_config,
+            rule_book_info,
+            rule_content,
+            rule_explore,
+            rule_search,
+            rule_toc,
+        },
+        converter.warnings,
+    ))
+}
+
+fn value_to_string(value: Option<&Value>) -> String {
+    match value {
+        None | Some(Value::Null) => "".to_string(),
+        Some(Value::String(s)) => s.trim().to_string(),
+        Some(other) => other.to_string(),
+    }
+}
+
+fn is_empty_value(value: &Value) -> bool {
+    match value {
+        Value::Null => true,
+        Value::String(s) => s.trim().is_empty(),
+        Value::Array(list) => list.is_empty(),
+        Value::Object(object) => object.is_empty(),
+        _ => false,
+    }
+}
+
+fn field_name(prefix: &str, key: &str) -> String {
+    if prefix.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", prefix, key)
+    }
+}
+
+struct Converter {
+    name: String,
+    warnings: Vec<ImportWarning>,
+}
+
+impl Converter {
+    fn warn(&mut self, field: &str, message: impl Into<String>) {
+        self.warnings.push(ImportWarning {
+            book_source_name: self.name.clone(),
+            field: field.to_string(),
+            message: message.into(),
+        });
+    }
+
+    fn take_str(&mut self, object: &mut Map<String, Value>, prefix: &str, key: &str) -> String {
+        match object.remove(key) {
+            Some(value @ (Value::Array(_) | Value::Object(_))) => {
+                self.warn(&field_name(prefix, key), format!("无法识别的值: {}", value));
+                "".to_string()
+            }
+            value => value_to_string(value.as_ref()),
+        }
+    }
+
+    /// 取出规则对象，旧版阅读中规则对象保存为 JSON 字符串
+    fn take_object(
+        &mut self,
+        object: &mut Map<String, Value>,
+        key: &str,
+    ) -> Option<Map<String, Value>> {
+        match object.remove(key)? {
+            Value::Object(rule) => Some(rule),
+            Value::String(rule) if rule.trim().is_empty() => None,
+            Value::String(rule) => {
+                let rule = parse_lenient_object(&rule);
+                if rule.is_none() {
+                    self.warn(key, "规则不是有效的 JSON 对象");
+                }
+                rule
+            }
+            Value::Null => None,
+            other => {
+                self.warn(key, format!("无法识别的值: {}", other));
+                None
+            }
+        }
+    }
+
+    fn take_rule(&mut self, object: &mut Map<String, Value>, prefix: &str, key: &str) -> String {
+        let rule = self.take_str(object, prefix, key);
+        self.convert_rule(&field_name(prefix, key), &rule)
+    }
+
+    /// 列表规则，阅读中以 `-` 开头表示倒序，同时返回是否倒序
+    fn take_list_rule(
+        &mut self,
+        object: &mut Map<String, Value>,
+        prefix: &str,
+        key: &str,
+    ) -> (String, bool) {
+        let field = field_name(prefix, key);
+        let rule = self.take_str(object, prefix, key);
+        let (rule, reverse) = match rule.strip_prefix('-') {
+            Some(rule) => (rule, true),
+            None => (rule.strip_prefix('+').unwrap_or(&rule), false),
+        };
+        if rule.starts_with(':') {
+            self.warn(&field, "不支持 `:` 开头的 AllInOne 正则规则");
+        }
+        (self.convert_rule(&field, rule), reverse)
+    }
+
+    fn convert_rule(&mut self, field: &str, rule: &str) -> String {
+        let rule = self.convert_common(field, rule);
+        if split_js(&rule).is_some() || !TEXT_SELECTOR.is_match(&rule) {
+            return rule;
+        }
+
+        // 转换成 XPath，有多个匹配时取第一个，避免页面顶部和底部的“下一页”得到两个链接
+        let Some(captures) = SIMPLE_TEXT_SELECTOR.captures(&rule) else {
+            self.warn(field, "不支持 `text.` 文本选择器");
+            return rule;
+        };
+        let value = match &captures[2] {
+            "text" => "text()".to_string(),
+            attr => format!("@{}", attr),
+        };
+        format!(
+            "@xpath:(//*[text()[contains(., '{}')]])[1]/{}",
+            &captures[1], value
+        )
+    }
+
+    /// 规则和链接共用的转换，检查脚本并统一规则前缀的大小写
+    fn convert_common(&mut self, field: &str, rule: &str) -> String {
+        if rule.is_empty() {
+            return "".to_string();
+        }
+
+        if rule.contains("%%") {
+            self.warn(field, "不支持 `%%` 交替合并规则");
+        }
+
+        if split_js(rule).is_some() {
+            if !cfg!(feature = "js") {
+                self.warn(field, "脚本规则需要开启 js feature");
+            }
+            let unsupported = JAVA_CALL
+                .captures_iter(rule)
+                .filter(|captures| {
+                    &captures[1] != "java" || !JAVA_FUNCTIONS.contains(&&captures[2])
+                })
+                .map(|captures| format!("{}.{}", &captures[1], &captures[2]))
+                .collect::<Vec<_>>();
+            if !unsupported.is_empty() {
+                self.warn(
+                    field,
+                    format!("脚本调用了不支持的函数: {}", unsupported.join(", ")),
+                );
+            }
+        }
+
+        // 阅读的规则前缀不区分大小写，脚本中的内容保持不变
+        let mut res = String::new();
+        let mut last = 0;
+        for region in JS_REGION
+            .find_iter(rule)
+            .map(|m| (m.start(), m.end()))
+            .chain([(rule.len(), rule.len())])
+        {
+            let (start, end) = region;
+            res.push_str(
+                &RULE_PREFIX.replace_all(&rule[last..start], |captures: &regex::Captures| {
+                    captures[0].to_lowercase()
+                }),
+            );
+            res.push_str(&rule[start..end]);
+            last = end;
+        }
+
+        OWN_TEXT.replace_all(&res, "@textNodes").to_string()
+    }
+
+    fn convert_url(&mut self, field: &str, url: &str) -> String {
+        let (path, options) = split_url_options(url);
+        if let Some(options) = options {
+            self.check_url_options(field, options);
+        }
+        if PAGE_TEMPLATE.is_match(&path) {
+            self.warn(field, "不支持 `<,{{page}}>` 页码模板");
+        }
+        self.convert_common(field, url)
+    }
+
+    /// 检查链接后缀的请求选项，见 [super::RequestOptions]
+    fn check_url_options(&mut self, field: &str, options: Map<String, Value>) {
+        let unsupported = options
+            .into_iter()
+            .filter(|(key, value)| {
+                !is_empty_value(value) && !REQUEST_OPTIONS.contains(&key.as_str())
+            })
+            .map(|(key, _)| key)
+            .collect::<Vec<_>>();
+        if !unsupported.is_empty() {
+            self.warn(
+                field,
+                format!("暂不支持请求选项: {}", unsupported.join(", ")),
+            );
+        }
+    }
+
+    fn convert_header(&mut self, header: Option<Value>) -> Option<String> {
+        let header = match header? {
+            Value::Object(header) => header,
+            Value::String(header) if header.trim().is_empty() => return None,
+            Value::String(header) => {
+                if split_js(&header).is_some() {
+                    self.warn("header", "不支持脚本生成请求头");
+                    return None;
+                }
+                let Some(header) = parse_lenient_object(&header) else {
+                    self.warn("header", "请求头不是有效的 JSON 对象");
+                    return None;
+                };
+                header
+            }
+            _ => return None,
+        };
+
+        let header = header
+            .iter()
+            .map(|(key, value)| (key.clone(), value_to_string(Some(value))))
+            .collect::<HashMap<_, _>>();
+        serde_json::to_string(&header).ok()
+    }
+
+    /// `concurrentRate` 为 `间隔毫秒` 或 `次数/毫秒`
+    fn convert_rate(&mut self, rate: Option<Value>) -> Option<RateLimit> {
+        let rate = value_to_string(rate.as_ref());
+        if rate.is_empty() || rate == "0" {
+            return None;
+        }
+
+        let (count, millis) = match rate.split_once('/') {
+            Some((count, millis)) => (count.trim().parse::<u64>(), millis.trim().parse::<f64>()),
+            None => (Ok(1), rate.parse::<f64>()),
+        };
+        match (count, millis) {
+            (Ok(count), Ok(millis)) if count > 0 && millis > 0.0 => Some(RateLimit {
+                max_count: count,
+                fill_duration: millis / 1000.0 / count as f64,
+            }),
+            _ => {
+                self.warn("concurrentRate", format!("无法识别的请求频率: {}", rate));
+                None
+            }
+        }
+    }
+
+    fn convert_explore_url(
+        &mut self,
+        explore_url: Option<Value>,
+    ) -> (Option<String>, Option<RuleExploreItem>) {
+        let list = match explore_url {
+            Some(Value::Array(list)) => list,
+            Some(Value::String(explore_url)) => {
+                let explore_url = explore_url.trim();
+                if explore_url.is_empty() {
+                    return (None, None);
+                }
+                if split_js(explore_url).is_some() {
+                    // 脚本生成的分类，交给 RuleExploreItem 解析脚本返回的列表
+                    let rule = match explore_url.strip_prefix("@js:") {
+                        Some(code) => format!("<js>{}</js>", code),
+                        None => explore_url.to_string(),
+                    };
+                    let rule = self.convert_rule("exploreUrl", &rule);
+                    return (
+                        Some(format!("{}{}", rule, EXPLORE_JS)),
+                        Some(RuleExploreItem {
+                            title: "$.title".into(),
+                            url: "$.url".into(),
+                        }),
+                    );
+                }
+                match serde_json::from_str::<Vec<Value>>(explore_url) {
+                    Ok(list) => list,
+                    Err(_) => explore_url
+                        .split("&&")
+                        .flat_map(|item| item.split('\n'))
+                        .filter_map(|item| item.split_once("::"))
+                        .map(|(title, url)| serde_json::json!({ "title": title, "url": url }))
+                        .collect(),
+                }
+            }
+            _ => return (None, None),
+        };
+
+        // 没有链接的项是阅读中的分组标题
+        let mut explores = vec![];
+        for item in list {
+            let title = value_to_string(item.get("title"));
+            let url = value_to_string(item.get("url"));
+            if url.is_empty() {
+                continue;
+            }
+            let url = self.convert_url(&format!("exploreUrl[{}]", title), &url);
+            explores.push(serde_json::json!({ "title": title, "url": url }));
+        }
+
+        if explores.is_empty() {
+            return (None, None);
+        }
+        (Some(Value::Array(explores).to_string()), None)
+    }
+
+    fn convert_rule_book_info(
+        &mut self,
+        prefix: &str,
+        rule: &mut Map<String, Value>,
+    ) -> RuleBookInfo {
+        if !value_to_string(rule.remove("init").as_ref()).is_empty() {
+            self.warn(&field_name(prefix, "init"), "不支持预处理规则");
+        }
+
+        RuleBookInfo {
+            name: self.take_rule(rule, prefix, "name"),
+            author: self.take_rule(rule, prefix, "author"),
+            cover_url: self.take_rule(rule, prefix, "coverUrl"),
+            intro: self.take_rule(rule, prefix, "intro"),
+            kind: self.take_rule(rule, prefix, "kind"),
+            last_chapter: self.take_rule(rule, prefix, "lastChapter"),
+            toc_url: self.take_rule(rule, prefix, "tocUrl"),
+            word_count: self.take_rule(rule, prefix, "wordCount"),
+            update_time: self.take_rule(rule, prefix, "updateTime"),
+            ..Default::default()
+        }
+    }
+
+    fn convert_rule_search(&mut self, prefix: &str, rule: &mut Map<String, Value>) -> RuleSearch {
+        let (book_list, reverse) = self.take_list_rule(rule, prefix, "bookList");
+        if reverse {
+            self.warn(
+                &field_name(prefix, "bookList"),
+                "不支持 `-` 倒序，已按原顺序解析",
+            );
+        }
+        let rule_search = RuleSearch {
+            book_list,
+            book_url: self.take_rule(rule, prefix, "bookUrl"),
+            book_info: self.convert_rule_book_info(prefix, rule),
+        };
+        self.check_rest(prefix, std::mem::take(rule), &["checkKeyWord"]);
+        rule_search
+    }
+
+    fn convert_rule_toc(&mut self, rule: &mut Map<String, Value>) -> RuleToc {
+        let (chapter_list, reverse) = self.take_list_rule(rule, "ruleToc", "chapterList");
+        let rule_toc = RuleToc {
+            chapter_list,
+            chapter_name: self.take_rule(rule, "ruleToc", "chapterName"),
+            chapter_url: self.take_rule(rule, "ruleToc", "chapterUrl"),
+            next_toc_url: self.take_rule(rule, "ruleToc", "nextTocUrl"),
+            page_count: String::new(),
+            reverse,
+            // 阅读总是按章节链接去重
+            dedup: true,
+            is_volume: self.take_rule(rule, "ruleToc", "isVolume"),
+            is_vip: self.take_rule(rule, "ruleToc", "isVip"),
+            update_time: self.take_rule(rule, "ruleToc", "updateTime"),
+            word_count: self.take_rule(rule, "ruleToc", "wordCount"),
+        };
+        self.check_rest("ruleToc", std::mem::take(rule), &[]);
+        rule_toc
+    }
+
+    fn convert_rule_content(&mut self, rule: &mut Map<String, Value>) -> RuleContent {
+        let content = self.take_rule(rule, "ruleContent", "content");
+
+        // 阅读的替换规则是 `##正则##替换内容`，去掉开头的 `##` 就是替换规则的简写
+        let mut cleanup = ContentCleanup::default();
+        let replace_regex = self.take_str(rule, "ruleContent", "replaceRegex");
+        if !replace_regex.is_empty() {
+            match replace_regex.strip_prefix("##") {
+                Some(rest) if !rest.is_empty() => {
+                    let rest = match rest.strip_suffix("###") {
+                        Some(rest) => {
+                            self.warn(
+                                "ruleContent.replaceRegex",
+                                "不支持 `###` 只替换第一个匹配，已替换全部匹配",
+                            );
+                            rest
+                        }
+                        None => rest,
+                    };
+                    cleanup
+                        .replace_regex
+                        .push(ReplaceRule::Regex(rest.to_string()));
+                }
+                _ => self.warn("ruleContent.replaceRegex", "只支持 `##正则##替换内容` 格式"),
+            }
+        }
+
+        // 阅读一直翻页到下一章为止，对应按链接判断是否还是本章的翻页方式
+        let next_content_url = self.take_rule(rule, "ruleContent", "nextContentUrl");
+
+        self.check_rest("ruleContent", std::mem::take(rule), &["imageStyle"]);
+        if next_content_url.is_empty() {
+            RuleContent::One { content, cleanup }
+        } else {
+            RuleContent::Follow {
+                content,
+                next_content_url,
+                max_pages: None,
+                cleanup,
+            }
+        }
+    }
+
+    /// 记录剩余未转换的字段
+    fn check_rest(&mut self, prefix: &str, rest: Map<String, Value>, ignored: &[&str]) {
+        for (key, value) in rest {
+            if ignored.contains(&key.as_str()) || is_empty_value(&value) {
+                continue;
+            }
+            self.warn(&field_name(prefix, &key), "暂不支持该字段");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_from_legado() {
+        let value = json!({
+            "bookSourceType": 0,
+            "bookSourceName": "示例书源",
+            "bookSourceUrl": "https://example.com",
+            "bookSourceGroup": "网站",
+            "enabled": true,
+            "lastUpdateTime": 1700000000000u64,
+            "concurrentRate": "3/1500",
+            "header": "{'User-Agent': 'Mozilla/5.0'}",
+            "searchUrl": "/search.php,{\"charset\": \"gbk\", \"method\": \"POST\", \"body\": \"q={{key}}&p={{page}}\", \"webView\": true}",
+            "exploreUrl": "分类::\n玄幻::/xuanhuan/{{page}}.html&&都市::/dushi/{{page}}.html",
+            "ruleExplore": {},
+            "ruleSearch": {
+                "bookList": "class.result-list@tag.li",
+                "name": "tag.h3@text",
+                "author": "class.author@ownText",
+                "bookUrl": "tag.a@href",
+                "checkKeyWord": "斗破"
+            },
+            "ruleBookInfo": "{\"tocUrl\": \"id.toc@href\", \"updateTime\": \"class.time@text\"}",
+            "ruleToc": {
+                "chapterList": "-id.list@tag.dd",
+                "chapterName": "tag.a@text",
+                "chapterUrl": "tag.a@href",
+                "nextTocUrl": "text.下一页@href",
+                "updateTime": "@CSS:.time@text",
+                "isVolume": "class.volume@text",
+                "isVip": "class.vip@text"
+            },
+            "ruleContent": {
+                "content": "@XPath://div[@id='content']",
+                "replaceRegex": "##本章未完",
+                "nextContentUrl": "text.下一页.0@href"
+            }
+        });
+
+        assert!(is_legado(&value));
+        let (book_source, warnings) = from_legado(&value).unwrap();
+
+        assert_eq!(
+            book_source.search_url,
+            r#"/search.php,{"charset": "gbk", "method": "POST", "body": "q={{key}}&p={{page}}", "webView": true}"#
+        );
+        let rate_limit = book_source.http_config.rate_limit.unwrap();
+        assert_eq!(rate_limit.max_count, 3);
+        assert_eq!(rate_limit.fill_duration, 0.5);
+        assert_eq!(
+            book_source.header.as_deref(),
+            Some(r#"{"User-Agent":"Mozilla/5.0"}"#)
+        );
+
+        let explores: Vec<crate::ExploreItem> =
+            serde_json::from_str(book_source.explore_url.as_ref().unwrap()).unwrap();
+        assert_eq!(explores.len(), 2);
+        assert_eq!(explores[1].title, "都市");
+        assert_eq!(explores[1].url, "/dushi/{{page}}.html");
+        assert_eq!(
+            book_source.rule_explore.unwrap().book_list,
+            "class.result-list@tag.li"
+        );
+
+        assert_eq!(
+            book_source.rule_search.book_info.author,
+            "class.author@textNodes"
+        );
+        assert_eq!(book_source.rule_book_info.toc_url, "id.toc@href");
+        assert_eq!(book_source.rule_book_info.update_time, "class.time@text");
+        assert_eq!(book_source.rule_toc.chapter_list, "id.list@tag.dd");
+        assert_eq!(
+            book_source.rule_toc.next_toc_url,
+            "@xpath:(//*[text()[contains(., '下一页')]])[1]/@href"
+        );
+        assert_eq!(book_source.rule_toc.update_time, "@css:.time@text");
+        assert!(book_source.rule_toc.reverse);
+        assert_eq!(book_source.rule_toc.is_volume, "class.volume@text");
+        assert_eq!(book_source.rule_toc.is_vip, "class.vip@text");
+        assert!(matches!(
+            book_source.rule_content,
+            RuleContent::Follow { ref content, ref next_content_url, ref cleanup, .. }
+                if content == "@xpath://div[@id='content']"
+                    && next_content_url == "text.下一页.0@href"
+                    && cleanup.replace_regex == vec![ReplaceRule::Regex("本章未完".into())]
+        ));
+
+        let fields = warnings
+            .iter()
+            .map(|warning| warning.field.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(fields, vec!["searchUrl", "ruleContent.nextContentUrl"]);
+
+        let html =
+            r#"<div><a href="/2.html">下一页</a><p>正文</p><a href="/2.html">下一页</a></div>"#;
+        let analyzer = crate::AnalyzerManager::new().unwrap();
+        assert!(analyzer.compile(&book_source.rule_toc.next_toc_url).is_ok());
+        assert_eq!(
+            analyzer
+                .clone()
+                .get_string(&book_source.rule_toc.next_toc_url, html, None)
+                .unwrap(),
+            "/2.html"
+        );
+    }
+
+    #[test]
+    fn test_import_invalid_cleanup() {
+        let value = json!({
+            "bookSourceName": "测试",
+            "bookSourceUrl": "https://example.com",
+            "bookSourceType": 0,
+            "searchUrl": "/search?q={{key}}",
+            "ruleContent": { "content": "id.content@html", "replaceRegex": "##(" }
+        });
+        let report = BookSource::import_from_json(value).unwrap();
+        assert_eq!(report.book_sources.len(), 1);
+        assert!(report
+            .warnings
+            .iter()
+            .any(|warning| warning.field == "ruleContent" && warning.message.contains("规则无效")));
+    }
+
+    #[test]
+    fn test_from_legado_explore_json() {
+        let value = json!({
+            "bookSourceUrl": "https://example.com",
+            "bookSourceType": 0,
+            "exploreUrl": "[{\"title\":\"排行\",\"url\":\"\"},{\"title\":\"月榜\",\"url\":\"/rank/month\",\"style\":{\"layout_flexGrow\":1}}]",
+            "searchUrl": "@js:java.ajax(baseUrl)"
+        });
+
+        let (book_source, warnings) = from_legado(&value).unwrap();
+        assert_eq!(
+            book_source.explore_url.as_deref(),
+            Some(r#"[{"title":"月榜","url":"/rank/month"}]"#)
+        );
+        assert!(warnings
+            .iter()
+            .any(|warning| warning.field == "searchUrl" && warning.message.contains("java.ajax")));
+
+        let value = json!({
+            "bookSourceUrl": "https://example.com",
+            "bookSourceType": 1,
+        });
+        assert!(from_legado(&value).is_err());
+    }
+
+    #[cfg(feature = "js")]
+    #[test]
+    fn test_from_legado_explore_js() {
+        use crate::AnalyzerManager;
+
+        let value = json!({
+            "bookSourceUrl": "https://example.com",
+            "bookSourceType": 0,
+            "exploreUrl": "@js:JSON.stringify([{title: \"玄幻\", url: \"/xh/{{page}}\"}, {title: \"分组\"}])",
+        });
+        let (book_source, warnings) = from_legado(&value).unwrap();
+        assert!(warnings.is_empty());
+
+        let rule_explore_item = book_source.rule_explore_item.unwrap();
+        let mut analyzer = AnalyzerManager::new().unwrap();
+        let list = analyzer
+            .get_element(book_source.explore_url.as_ref().unwrap(), "")
+            .unwrap();
+        assert_eq!(list.len(), 1);
+        assert_eq!(
+            analyzer
+                .get_string(&rule_explore_item.url, &list[0], None)
+                .unwrap(),
+            "/xh/{{page}}"
+        );
+    }
+}
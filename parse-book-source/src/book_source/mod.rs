@@ -1,11 +1,12 @@
-use crate::Result;
+use crate::{AnalyzerManager, Result};
 use anyhow::anyhow;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::path::Path;
+use std::{fmt::Display, path::Path};
 
 pub mod http_config;
 pub use http_config::*;
+pub mod legado;
 pub mod rule;
 pub use rule::*;
 
@@ -24,6 +25,13 @@ pub struct BookSource {
     pub header: Option<String>,
     pub respond_time: Option<u64>,
 
+    /// 登录链接，支持请求选项，`{{username}}`、`{{password}}` 为 url 编码后的账号和密码，
+    /// `{{rawUsername}}`、`{{rawPassword}}` 为原始值，
+    /// 例如 `/login,{"method":"POST","body":"user={{username}}&pass={{password}}"}`
+    pub login_url: Option<String>,
+    /// 登录结果规则，作用于登录之后的页面，结果为空时视为登录失败
+    pub login_check: Option<String>,
+
     #[serde(default)]
     pub http_config: HttpConfig,
 
@@ -35,30 +43,176 @@ pub struct BookSource {
     pub rule_toc: RuleToc,
 }
 
+/// 导入书源时无法转换或解析失败的字段
+#[derive(Debug, Clone)]
+pub struct ImportWarning {
+    pub book_source_name: String,
+    /// 字段路径，例如 `ruleToc.nextTocUrl`，整个书源解析失败时为空
+    pub field: String,
+    pub message: String,
+}
+
+impl Display for ImportWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.field.is_empty() {
+            write!(f, "[{}] {}", self.book_source_name, self.message)
+        } else {
+            write!(
+                f,
+                "[{}] {}: {}",
+                self.book_source_name, self.field, self.message
+            )
+        }
+    }
+}
+
+/// 导入结果，包含成功导入的书源和转换报告
+#[derive(Debug, Clone, Default)]
+pub struct ImportReport {
+    pub book_sources: Vec<BookSource>,
+    pub warnings: Vec<ImportWarning>,
+}
+
 impl BookSource {
     pub async fn from_url(url: &str) -> Result<Vec<Self>> {
-        let res: Value = reqwest::get(url).await?.json().await?;
-        Self::from_json(res)
+        Ok(Self::import_from_url(url).await?.book_sources)
     }
 
     pub fn from_path<T: AsRef<Path>>(path: T) -> Result<Vec<Self>> {
+        Ok(Self::import_from_path(path)?.book_sources)
+    }
+
+    pub fn from_json(value: Value) -> Result<Vec<Self>> {
+        Ok(Self::import_from_json(value)?.book_sources)
+    }
+
+    pub async fn import_from_url(url: &str) -> Result<ImportReport> {
+        let res: Value = reqwest::get(url).await?.json().await?;
+        Self::import_from_json(res)
+    }
+
+    pub fn import_from_path<T: AsRef<Path>>(path: T) -> Result<ImportReport> {
         let file = std::fs::File::open(path)?;
         let res: Value = serde_json::from_reader(file)?;
-        Self::from_json(res)
+        Self::import_from_json(res)
     }
 
-    pub fn from_json(value: Value) -> Result<Vec<Self>> {
-        if value.is_object() {
-            Ok(vec![serde_json::from_value(value)?])
-        } else if value.is_array() {
-            Ok(value
-                .as_array()
-                .unwrap()
-                .iter()
-                .filter_map(|item| serde_json::from_value(item.clone()).ok())
-                .collect())
+    /// 解析书源，支持 TRNovel 和阅读（Legado）格式，数组中解析失败的书源记录在报告中
+    pub fn import_from_json(value: Value) -> Result<ImportReport> {
+        let analyzer = AnalyzerManager::new()?;
+        let mut report = ImportReport::default();
+        match value {
+            Value::Object(_) => {
+                let (book_source, warnings) = Self::parse_one(&value, &analyzer)?;
+                report.book_sources.push(book_source);
+                report.warnings.extend(warnings);
+            }
+            Value::Array(list) => {
+                for item in list {
+                    match Self::parse_one(&item, &analyzer) {
+                        Ok((book_source, warnings)) => {
+                            report.book_sources.push(book_source);
+                            report.warnings.extend(warnings);
+                        }
+                        Err(e) => report.warnings.push(ImportWarning {
+                            book_source_name: item
+                                .get("bookSourceName")
+                                .and_then(|name| name.as_str())
+                                .unwrap_or_default()
+                                .to_string(),
+                            field: "".to_string(),
+                            message: format!("解析失败: {}", e),
+                        }),
+                    }
+                }
+            }
+            _ => return Err(anyhow!("value is not object or array").into()),
+        }
+        Ok(report)
+    }
+
+    /// 解析单个书源，无效的规则记录在报告中
+    fn parse_one(value: &Value, analyzer: &AnalyzerManager) -> Result<(Self, Vec<ImportWarning>)> {
+        let (book_source, mut warnings): (Self, _) = if legado::is_legado(value) {
+            legado::from_legado(value)?
         } else {
-            Err(anyhow!("value is not object or array").into())
+            (serde_json::from_value(value.clone())?, vec![])
+        };
+
+        for (field, rule) in book_source.rules() {
+            if let Err(e) = analyzer.compile(rule) {
+                warnings.push(ImportWarning {
+                    book_source_name: book_source.book_source_name.clone(),
+                    field,
+                    message: format!("规则无效: {}", e),
+                });
+            }
+        }
+        if let Err(e) = book_source.rule_content.cleanup().compile() {
+            warnings.push(ImportWarning {
+                book_source_name: book_source.book_source_name.clone(),
+                field: "ruleContent".to_string(),
+                message: format!("规则无效: {}", e),
+            });
+        }
+        Ok((book_source, warnings))
+    }
+
+    /// 书源中的所有解析规则，字段名为 `ruleToc.chapterList` 的形式
+    pub fn rules(&self) -> Vec<(String, &str)> {
+        let mut rules = vec![];
+        let mut groups = vec![
+            ("ruleSearch", self.rule_search.rules()),
+            ("ruleBookInfo", self.rule_book_info.rules()),
+            ("ruleToc", self.rule_toc.rules()),
+            ("ruleContent", self.rule_content.rules()),
+        ];
+        if let Some(rule_explore) = &self.rule_explore {
+            groups.push(("ruleExplore", rule_explore.rules()));
+        }
+        if let Some(login_check) = &self.login_check {
+            rules.push(("loginCheck".to_string(), login_check.as_str()));
+        }
+        // 没有 ruleExploreItem 时 exploreUrl 是分类列表，不是规则
+        if let Some(rule_explore_item) = &self.rule_explore_item {
+            if let Some(explore_url) = &self.explore_url {
+                rules.push(("exploreUrl".to_string(), explore_url.as_str()));
+            }
+            groups.push(("ruleExploreItem", rule_explore_item.rules()));
+        }
+
+        for (prefix, list) in groups {
+            for (field, rule) in list {
+                rules.push((format!("{}.{}", prefix, field), rule));
+            }
+        }
+
+        let mut book_infos = vec![
+            ("ruleSearch", &self.rule_search.book_info),
+            ("ruleBookInfo", &self.rule_book_info),
+        ];
+        if let Some(rule_explore) = &self.rule_explore {
+            book_infos.push(("ruleExplore", &rule_explore.book_info));
+        }
+        for (prefix, book_info) in book_infos {
+            for (field, rule) in book_info.extra_rules() {
+                rules.push((format!("{}.{}", prefix, field), rule));
+            }
+        }
+        rules
+    }
+
+    /// 预先编译所有规则，规则无效时返回错误
+    pub fn compile(&self, analyzer: &AnalyzerManager) -> Result<()> {
+        for (field, rule) in self.rules() {
+            analyzer
+                .compile(rule)
+                .map_err(|e| anyhow!("invalid rule {}: {}", field, e))?;
         }
+        self.rule_content
+            .cleanup()
+            .compile()
+            .map_err(|e| anyhow!("invalid rule ruleContent: {}", e))?;
+        Ok(())
     }
 }
@@ -1,5 +1,9 @@
-use crate::{AnalyzerManager, BookInfo, BookListItem, Chapter, ExploreItem, Result};
+use crate::{
+    utils::compile_regex, AnalyzerManager, BookInfo, BookListItem, Chapter, ExploreItem,
+    HttpClient, Result, RuleContext,
+};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -11,14 +15,31 @@ pub struct RuleSearch {
 }
 
 impl RuleSearch {
-    pub fn parse_to_book_list_item(
+    /// 字段名和规则
+    pub fn rules(&self) -> Vec<(&'static str, &str)> {
+        let mut rules = vec![
+            ("bookList", self.book_list.as_str()),
+            ("bookUrl", &self.book_url),
+        ];
+        rules.extend(self.book_info.rules());
+        rules
+    }
+
+    pub async fn parse_to_book_list_item(
         &self,
         analyzer: &mut AnalyzerManager,
+        http_client: &HttpClient,
         content: &str,
     ) -> Result<BookListItem> {
         Ok(BookListItem {
-            book_url: analyzer.get_string(&self.book_url, content, None)?,
-            book_info: self.book_info.parse_to_book_info(analyzer, content)?,
+            book_url: analyzer
+                .get_string_async(&self.book_url, content, None, http_client)
+                .await
+                .field("bookUrl")?,
+            book_info: self
+                .book_info
+                .parse_to_book_info(analyzer, http_client, content)
+                .await?,
         })
     }
 }
@@ -31,19 +52,31 @@ pub struct RuleExploreItem {
 }
 
 impl RuleExploreItem {
-    pub fn parse_to_explore_item(
+    /// 字段名和规则
+    pub fn rules(&self) -> Vec<(&'static str, &str)> {
+        vec![("title", &self.title), ("url", &self.url)]
+    }
+
+    pub async fn parse_to_explore_item(
         &self,
         analyzer: &mut AnalyzerManager,
+        http_client: &HttpClient,
         content: &str,
     ) -> Result<ExploreItem> {
         Ok(ExploreItem {
-            title: analyzer.get_string(&self.title, content, None)?,
-            url: analyzer.get_string(&self.url, content, None)?,
+            title: analyzer
+                .get_string_async(&self.title, content, None, http_client)
+                .await
+                .field("title")?,
+            url: analyzer
+                .get_string_async(&self.url, content, None, http_client)
+                .await
+                .field("url")?,
         })
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct RuleBookInfo {
     pub name: String,
@@ -60,48 +93,242 @@ pub struct RuleBookInfo {
     pub toc_url: String,
     #[serde(default)]
     pub word_count: String,
+    /// 连载状态，例如连载、完结
+    #[serde(default)]
+    pub status: String,
+    #[serde(default)]
+    pub update_time: String,
+    /// 标签，多个标签用逗号、顿号或者空白分隔，见 [split_tags]
+    #[serde(default)]
+    pub tags: String,
+    /// 评分
+    #[serde(default)]
+    pub rating: String,
+    /// 其他信息，键为显示的名称，值为规则
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub extra: BTreeMap<String, String>,
 }
 
 impl RuleBookInfo {
-    pub fn parse_to_book_info(
+    /// 字段名和规则
+    pub fn rules(&self) -> Vec<(&'static str, &str)> {
+        vec![
+            ("name", &self.name),
+            ("author", &self.author),
+            ("coverUrl", &self.cover_url),
+            ("intro", &self.intro),
+            ("kind", &self.kind),
+            ("lastChapter", &self.last_chapter),
+            ("tocUrl", &self.toc_url),
+            ("wordCount", &self.word_count),
+            ("status", &self.status),
+            ("updateTime", &self.update_time),
+            ("tags", &self.tags),
+            ("rating", &self.rating),
+        ]
+    }
+
+    /// `extra` 中的规则，字段名为 `extra.键` 的形式
+    pub fn extra_rules(&self) -> Vec<(String, &str)> {
+        self.extra
+            .iter()
+            .map(|(key, rule)| (format!("extra.{}", key), rule.as_str()))
+            .collect()
+    }
+
+    pub async fn parse_to_book_info(
         &self,
         analyzer: &mut AnalyzerManager,
+        http_client: &HttpClient,
         content: &str,
     ) -> Result<BookInfo> {
         Ok(BookInfo {
-            name: analyzer.get_string(&self.name, content, None)?,
-            author: analyzer.get_string(&self.author, content, None)?,
-            cover_url: analyzer.get_string(&self.cover_url, content, None)?,
-            intro: analyzer.get_string(&self.intro, content, None)?,
-            kind: analyzer.get_string(&self.kind, content, None)?,
-            last_chapter: analyzer.get_string(&self.last_chapter, content, None)?,
-            toc_url: analyzer.get_string(&self.toc_url, content, None)?,
-            word_count: analyzer.get_string(&self.word_count, content, None)?,
+            name: analyzer
+                .get_string_async(&self.name, content, None, http_client)
+                .await
+                .field("name")?,
+            author: analyzer
+                .get_string_async(&self.author, content, None, http_client)
+                .await
+                .field("author")?,
+            cover_url: analyzer
+                .get_string_async(&self.cover_url, content, None, http_client)
+                .await
+                .field("coverUrl")?,
+            intro: analyzer
+                .get_string_async(&self.intro, content, None, http_client)
+                .await
+                .field("intro")?,
+            kind: analyzer
+                .get_string_async(&self.kind, content, None, http_client)
+                .await
+                .field("kind")?,
+            last_chapter: analyzer
+                .get_string_async(&self.last_chapter, content, None, http_client)
+                .await
+                .field("lastChapter")?,
+            toc_url: analyzer
+                .get_string_async(&self.toc_url, content, None, http_client)
+                .await
+                .field("tocUrl")?,
+            word_count: analyzer
+                .get_string_async(&self.word_count, content, None, http_client)
+                .await
+                .field("wordCount")?,
+            status: analyzer
+                .get_string_async(&self.status, content, None, http_client)
+                .await
+                .field("status")?,
+            update_time: analyzer
+                .get_string_async(&self.update_time, content, None, http_client)
+                .await
+                .field("updateTime")?,
+            tags: split_tags(
+                &analyzer
+                    .get_string_async(&self.tags, content, None, http_client)
+                    .await
+                    .field("tags")?,
+            ),
+            rating: analyzer
+                .get_string_async(&self.rating, content, None, http_client)
+                .await
+                .field("rating")?,
+            extra: {
+                let mut extra = BTreeMap::new();
+                for (key, rule) in &self.extra {
+                    let value = analyzer
+                        .get_string_async(rule, content, None, http_client)
+                        .await
+                        .field(&format!("extra.{}", key))?;
+                    if !value.is_empty() {
+                        extra.insert(key.clone(), value);
+                    }
+                }
+                extra
+            },
         })
     }
 }
 
+/// 拆分标签，去掉空白和重复的标签
+pub fn split_tags(tags: &str) -> Vec<String> {
+    let mut res: Vec<String> = vec![];
+    for tag in tags.split(|c: char| c.is_whitespace() || ",，、|/".contains(c)) {
+        if !tag.is_empty() && !res.iter().any(|item| item == tag) {
+            res.push(tag.to_string());
+        }
+    }
+    res
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct RuleToc {
     pub chapter_list: String,
     pub chapter_name: String,
     pub chapter_url: String,
+    /// 下一页目录的链接，可以使用 `{{page}}` 表示下一页的页码，
+    /// 返回多个链接时依次请求，为空时停止翻页
+    #[serde(default)]
+    pub next_toc_url: String,
+    /// 目录的总页数，在第一页中解析，设置后最多请求这么多页
+    #[serde(default)]
+    pub page_count: String,
+    /// 是否倒序排列章节
+    #[serde(default)]
+    pub reverse: bool,
+    /// 是否按章节链接去重，保留第一次出现的章节
+    #[serde(default)]
+    pub dedup: bool,
+    /// 是否是分卷标题，结果不为空、`false`、`0` 时为真，见 [is_truthy]
+    #[serde(default)]
+    pub is_volume: String,
+    /// 是否是需要付费的章节，判断方式和 `isVolume` 相同
+    #[serde(default)]
+    pub is_vip: String,
+    #[serde(default)]
+    pub update_time: String,
+    #[serde(default)]
+    pub word_count: String,
 }
 
 impl RuleToc {
-    pub fn parse_to_chapter(
+    /// 字段名和规则
+    pub fn rules(&self) -> Vec<(&'static str, &str)> {
+        vec![
+            ("chapterList", &self.chapter_list),
+            ("chapterName", &self.chapter_name),
+            ("chapterUrl", &self.chapter_url),
+            ("nextTocUrl", &self.next_toc_url),
+            ("pageCount", &self.page_count),
+            ("isVolume", &self.is_volume),
+            ("isVip", &self.is_vip),
+            ("updateTime", &self.update_time),
+            ("wordCount", &self.word_count),
+        ]
+    }
+
+    /// 按规则的选项去重和排序章节
+    pub fn arrange(&self, mut chapters: Vec<Chapter>) -> Vec<Chapter> {
+        if self.dedup {
+            let mut urls = HashSet::new();
+            chapters.retain(|chapter| {
+                chapter.chapter_url.is_empty() || urls.insert(chapter.chapter_url.clone())
+            });
+        }
+        if self.reverse {
+            chapters.reverse();
+        }
+        chapters
+    }
+
+    pub async fn parse_to_chapter(
         &self,
         analyzer: &mut AnalyzerManager,
+        http_client: &HttpClient,
         content: &str,
     ) -> Result<Chapter> {
         Ok(Chapter {
-            chapter_name: analyzer.get_string(&self.chapter_name, content, None)?,
-            chapter_url: analyzer.get_string(&self.chapter_url, content, None)?,
+            chapter_name: analyzer
+                .get_string_async(&self.chapter_name, content, None, http_client)
+                .await
+                .field("chapterName")?,
+            chapter_url: analyzer
+                .get_string_async(&self.chapter_url, content, None, http_client)
+                .await
+                .field("chapterUrl")?,
+            is_volume: is_truthy(
+                &analyzer
+                    .get_string_async(&self.is_volume, content, None, http_client)
+                    .await
+                    .field("isVolume")?,
+            ),
+            is_vip: is_truthy(
+                &analyzer
+                    .get_string_async(&self.is_vip, content, None, http_client)
+                    .await
+                    .field("isVip")?,
+            ),
+            update_time: analyzer
+                .get_string_async(&self.update_time, content, None, http_client)
+                .await
+                .field("updateTime")?,
+            word_count: analyzer
+                .get_string_async(&self.word_count, content, None, http_client)
+                .await
+                .field("wordCount")?,
         })
     }
 }
 
+/// 判断规则结果是否为真，空字符串、`false`、`0` 和 `null` 为假
+pub fn is_truthy(value: &str) -> bool {
+    !matches!(
+        value.trim().to_lowercase().as_str(),
+        "" | "false" | "0" | "null"
+    )
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(untagged)]
 pub enum RuleContent {
@@ -111,8 +338,205 @@ pub enum RuleContent {
         next_content_url: String,
         start: usize,
         end: String,
+        #[serde(flatten)]
+        cleanup: ContentCleanup,
+    },
+    /// 没有页数时一直翻页，直到下一页的链接为空、重复、不再属于本章或者达到页数上限，
+    /// 见 [crate::utils::same_chapter]
+    #[serde(rename_all = "camelCase")]
+    Follow {
+        content: String,
+        next_content_url: String,
+        /// 最多请求的页数，默认 [crate::MAX_CONTENT_PAGES]
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max_pages: Option<usize>,
+        #[serde(flatten)]
+        cleanup: ContentCleanup,
     },
     One {
         content: String,
+        #[serde(flatten)]
+        cleanup: ContentCleanup,
     },
 }
+
+impl RuleContent {
+    /// 字段名和规则
+    pub fn rules(&self) -> Vec<(&'static str, &str)> {
+        match self {
+            RuleContent::More {
+                content,
+                next_content_url,
+                end,
+                ..
+            } => vec![
+                ("content", content),
+                ("nextContentUrl", next_content_url),
+                ("end", end),
+            ],
+            RuleContent::Follow {
+                content,
+                next_content_url,
+                ..
+            } => vec![("content", content), ("nextContentUrl", next_content_url)],
+            RuleContent::One { content, .. } => vec![("content", content)],
+        }
+    }
+
+    /// 获取正文之后的替换和净化规则
+    pub fn cleanup(&self) -> &ContentCleanup {
+        match self {
+            RuleContent::More { cleanup, .. }
+            | RuleContent::Follow { cleanup, .. }
+            | RuleContent::One { cleanup, .. } => cleanup,
+        }
+    }
+}
+
+/// 正文的替换规则
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum ReplaceRule {
+    /// 和规则后缀相同的格式 `正则##替换内容`，省略替换内容时删除匹配的内容
+    Regex(String),
+    /// 完整写法，`regex` 为 false 时按普通字符串替换
+    Full {
+        pattern: String,
+        #[serde(default)]
+        replacement: String,
+        #[serde(default = "default_true")]
+        regex: bool,
+    },
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl ReplaceRule {
+    fn apply(&self, content: &str) -> Result<String> {
+        match self {
+            ReplaceRule::Regex(rule) => {
+                let (pattern, replacement) = rule.split_once("##").unwrap_or((rule, ""));
+                Ok(compile_regex(pattern)?
+                    .replace_all(content, replacement)
+                    .to_string())
+            }
+            ReplaceRule::Full {
+                pattern,
+                replacement,
+                regex: true,
+            } => Ok(compile_regex(pattern)?
+                .replace_all(content, replacement.as_str())
+                .to_string()),
+            ReplaceRule::Full {
+                pattern,
+                replacement,
+                regex: false,
+            } => Ok(content.replace(pattern.as_str(), replacement)),
+        }
+    }
+}
+
+/// 获取正文之后依次执行的替换和净化规则，先执行所有替换规则，再执行净化规则
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ContentCleanup {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub replace_regex: Vec<ReplaceRule>,
+    /// 删除这些内容，例如广告和“请收藏本站”，以 `/` 开头和结尾时按正则匹配，
+    /// 删除之后只剩空白的行也一并删除
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub purify: Vec<String>,
+}
+
+impl ContentCleanup {
+    pub fn is_empty(&self) -> bool {
+        self.replace_regex.is_empty() && self.purify.is_empty()
+    }
+
+    /// 检查所有正则表达式是否有效
+    pub fn compile(&self) -> Result<()> {
+        self.apply("").map(|_| ())
+    }
+
+    pub fn apply(&self, content: &str) -> Result<String> {
+        let mut content = content.to_string();
+        for rule in &self.replace_regex {
+            content = rule.apply(&content).field("replaceRegex")?;
+        }
+        if self.purify.is_empty() {
+            return Ok(content);
+        }
+
+        let mut matchers = vec![];
+        for purify in &self.purify {
+            let matcher = match purify
+                .strip_prefix('/')
+                .and_then(|purify| purify.strip_suffix('/'))
+            {
+                Some(pattern) if !pattern.is_empty() => {
+                    Some(compile_regex(pattern).field("purify")?)
+                }
+                _ => None,
+            };
+            matchers.push((purify.as_str(), matcher));
+        }
+
+        // 正文经常只有一行，只删除匹配到的内容，不能删除整行
+        let mut lines = vec![];
+        for line in content.lines() {
+            let mut purified = line.to_string();
+            for (purify, matcher) in &matchers {
+                purified = match matcher {
+                    Some(regex) => regex.replace_all(&purified, "").into_owned(),
+                    None => purified.replace(purify, ""),
+                };
+            }
+            if purified == line || !purified.trim().is_empty() {
+                lines.push(purified);
+            }
+        }
+        Ok(lines.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_content_cleanup() {
+        let rule_content: RuleContent = serde_json::from_value(json!({
+            "content": "@css:#content@text",
+            "replaceRegex": [
+                "\\(本章未完.*?\\)",
+                "笔趣阁##",
+                { "pattern": "a.b", "replacement": "*", "regex": false }
+            ],
+            "purify": ["请收藏本站", "/^\\s*第\\d+页\\s*$/"]
+        }))
+        .unwrap();
+        assert!(matches!(rule_content, RuleContent::One { .. }));
+
+        let content = "第一段(本章未完，请翻页)\n请收藏本站\n\n笔趣阁a.b和axb\n  第2页 ";
+        assert_eq!(
+            rule_content.cleanup().apply(content).unwrap(),
+            "第一段\n\n*和axb"
+        );
+
+        // 整章只有一行时只删除匹配的内容
+        let content = "第一段  请收藏本站  第二段  第3页";
+        assert_eq!(
+            rule_content.cleanup().apply(content).unwrap(),
+            "第一段    第二段  第3页"
+        );
+
+        let cleanup = ContentCleanup {
+            purify: vec!["/(/".into()],
+            ..Default::default()
+        };
+        assert!(cleanup.compile().is_err());
+    }
+}
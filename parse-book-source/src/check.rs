@@ -0,0 +1,282 @@
+//! 书源检查，依次执行发现 → 搜索 → 详情 → 目录 → 第一章正文
+
+use crate::{BookSourceParser, Result, Stage};
+use anyhow::anyhow;
+use std::{
+    fmt::{self, Display},
+    time::{Duration, Instant},
+};
+
+/// 检查时每页获取的数量
+const PAGE_SIZE: u32 = 20;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StageStatus {
+    Passed,
+    /// 失败的原因
+    Failed(String),
+    /// 书源不支持该阶段或者前面的阶段失败
+    Skipped,
+}
+
+/// 单个阶段的检查结果
+#[derive(Debug, Clone)]
+pub struct StageReport {
+    pub stage: Stage,
+    pub status: StageStatus,
+    pub duration: Duration,
+    /// 获取到的数量，分别为分类数、书籍数、章节数和正文字数，详情为 1
+    pub count: usize,
+}
+
+impl StageReport {
+    fn unit(&self) -> &'static str {
+        match self.stage {
+            Stage::Explore => "个分类",
+            Stage::Search => "本书",
+            Stage::Info => "本书",
+            Stage::Toc => "章",
+            Stage::Content => "字",
+            Stage::Login => "",
+        }
+    }
+}
+
+impl Display for StageReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.status {
+            StageStatus::Passed => write!(
+                f,
+                "{:<8}通过  {} {}  {:.2}s",
+                self.stage,
+                self.count,
+                self.unit(),
+                self.duration.as_secs_f64()
+            ),
+            StageStatus::Failed(error) => {
+                write!(
+                    f,
+                    "{:<8}失败  {:.2}s",
+                    self.stage,
+                    self.duration.as_secs_f64()
+                )?;
+                for line in error.lines() {
+                    write!(f, "\n    {}", line)?;
+                }
+                Ok(())
+            }
+            StageStatus::Skipped => write!(f, "{:<8}跳过", self.stage),
+        }
+    }
+}
+
+/// 书源检查报告
+#[derive(Debug, Clone)]
+pub struct CheckReport {
+    pub book_source: String,
+    pub stages: Vec<StageReport>,
+}
+
+impl CheckReport {
+    /// 所有阶段都没有失败
+    pub fn is_ok(&self) -> bool {
+        self.first_error().is_none()
+    }
+
+    /// 第一个失败的阶段
+    pub fn first_error(&self) -> Option<&StageReport> {
+        self.stages
+            .iter()
+            .find(|stage| matches!(stage.status, StageStatus::Failed(_)))
+    }
+
+    /// 总耗时
+    pub fn duration(&self) -> Duration {
+        self.stages.iter().map(|stage| stage.duration).sum()
+    }
+
+    /// 记录阶段结果，数量为 0 时视为失败，成功时返回结果
+    fn record<T>(
+        &mut self,
+        stage: Stage,
+        start: Instant,
+        result: Result<T>,
+        count: impl Fn(&T) -> usize,
+    ) -> Option<T> {
+        let duration = start.elapsed();
+        let (status, count, value) = match result {
+            Ok(value) => match count(&value) {
+                0 => (StageStatus::Failed("结果为空".to_string()), 0, None),
+                count => (StageStatus::Passed, count, Some(value)),
+            },
+            Err(e) => (StageStatus::Failed(e.to_string()), 0, None),
+        };
+        self.stages.push(StageReport {
+            stage,
+            status,
+            duration,
+            count,
+        });
+        value
+    }
+
+    fn skip(&mut self, stages: &[Stage]) {
+        for stage in stages {
+            self.stages.push(StageReport {
+                stage: *stage,
+                status: StageStatus::Skipped,
+                duration: Duration::ZERO,
+                count: 0,
+            });
+        }
+    }
+}
+
+impl Display for CheckReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}  {}  {:.2}s",
+            self.book_source,
+            if self.is_ok() { "通过" } else { "失败" },
+            self.duration().as_secs_f64()
+        )?;
+        for stage in &self.stages {
+            write!(f, "\n  {}", stage)?;
+        }
+        Ok(())
+    }
+}
+
+impl BookSourceParser {
+    /// 检查书源是否可用，依次执行发现 → 搜索 → 详情 → 目录 → 第一章正文
+    /// 发现失败不影响后续阶段，其他阶段失败时跳过后面的阶段
+    pub async fn check(&mut self, keyword: &str) -> CheckReport {
+        let mut report = CheckReport {
+            book_source: self.book_source.book_source_name.clone(),
+            stages: vec![],
+        };
+
+        if self.book_source.explore_url.is_some() {
+            let start = Instant::now();
+            let result = self.check_explore().await;
+            report.record(Stage::Explore, start, result, |count| *count);
+        } else {
+            report.skip(&[Stage::Explore]);
+        }
+
+        let start = Instant::now();
+        let result = self.search_books(keyword, 1, PAGE_SIZE).await;
+        let Some(books) = report.record(Stage::Search, start, result, Vec::len) else {
+            report.skip(&[Stage::Info, Stage::Toc, Stage::Content]);
+            return report;
+        };
+
+        let start = Instant::now();
+        let result = self.get_book_info(&books[0].book_url).await;
+        let Some(book_info) = report.record(Stage::Info, start, result, |_| 1) else {
+            report.skip(&[Stage::Toc, Stage::Content]);
+            return report;
+        };
+
+        let start = Instant::now();
+        let result = self.get_chapters(&book_info.toc_url).await;
+        let Some(chapters) = report.record(Stage::Toc, start, result, Vec::len) else {
+            report.skip(&[Stage::Content]);
+            return report;
+        };
+
+        // 跳过分卷标题，优先检查免费章节
+        let start = Instant::now();
+        let readable = chapters
+            .iter()
+            .filter(|chapter| !chapter.is_volume && !chapter.chapter_url.trim().is_empty());
+        let chapter = readable
+            .clone()
+            .find(|chapter| !chapter.is_vip)
+            .or_else(|| readable.clone().next());
+        let result = match chapter {
+            Some(chapter) => self.get_content(&chapter.chapter_url).await,
+            None => Err(anyhow!("目录中没有可以阅读的章节").into()),
+        };
+        report.record(Stage::Content, start, result, |content| {
+            content.chars().filter(|c| !c.is_whitespace()).count()
+        });
+
+        report
+    }
+
+    /// 获取分类，并获取第一个分类的书籍，返回分类数量
+    async fn check_explore(&mut self) -> Result<usize> {
+        let explores = self.get_explores().await?;
+        if let (Some(explore), Some(_)) = (explores.first(), &self.book_source.rule_explore) {
+            self.explore_books(&explore.url, 1, PAGE_SIZE).await?;
+        }
+        Ok(explores.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        tests::book_source,
+        utils::test_server::{serve, TestResponse},
+    };
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_check() {
+        let base_url = serve(|req| {
+            let body = match req.path.split('?').next().unwrap_or_default() {
+                "/search" => {
+                    json!({ "list": [{ "name": "斗破苍穹", "author": "天蚕土豆", "url": "/book/1" }] })
+                }
+                "/book/1" => json!({ "name": "斗破苍穹", "author": "天蚕土豆", "toc": "/toc/1" }),
+                "/toc/1" => json!({ "list": [
+                    { "name": "第一卷", "volume": true },
+                    { "name": "第一章", "url": "/chapter/1" }
+                ] }),
+                "/chapter/1" => json!({ "content": "" }),
+                _ => return TestResponse::status(404),
+            };
+            TestResponse::ok(body.to_string())
+        });
+
+        let book_source = book_source(
+            &base_url,
+            json!({
+                "searchUrl": "/search?q={{key}}",
+                "ruleSearch": { "bookList": "$.list[*]", "bookUrl": "$.url", "name": "$.name", "author": "$.author" },
+                "ruleBookInfo": { "name": "$.name", "author": "$.author", "tocUrl": "$.toc" },
+                "ruleToc": { "chapterList": "$.list[*]", "chapterName": "$.name", "chapterUrl": "$.url", "isVolume": "$.volume" },
+                "ruleContent": { "content": "$.content" }
+            }),
+        );
+
+        let mut parser = BookSourceParser::new(book_source).unwrap();
+        let report = parser.check("斗破").await;
+
+        let status = report
+            .stages
+            .iter()
+            .map(|stage| (stage.stage, stage.count))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            status,
+            vec![
+                (Stage::Explore, 0),
+                (Stage::Search, 1),
+                (Stage::Info, 1),
+                (Stage::Toc, 2),
+                (Stage::Content, 0)
+            ]
+        );
+        assert_eq!(report.stages[0].status, StageStatus::Skipped);
+        // 跳过分卷标题，第一章的正文为空
+        let error = report.first_error().unwrap();
+        assert_eq!(error.stage, Stage::Content);
+        assert_eq!(error.status, StageStatus::Failed("结果为空".to_string()));
+        assert!(!report.is_ok());
+    }
+}
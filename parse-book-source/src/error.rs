@@ -1,3 +1,4 @@
+use std::fmt::{self, Display};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -25,6 +26,207 @@ pub enum ParseError {
 
     #[error("{0}")]
     Warning(String),
+
+    #[error("{0}")]
+    Rule(Box<RuleDiagnostic>),
 }
 
 pub type Result<T> = std::result::Result<T, ParseError>;
+
+/// 书源解析的阶段
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    Search,
+    Explore,
+    Info,
+    Toc,
+    Content,
+    Login,
+}
+
+impl Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Stage::Search => "search",
+            Stage::Explore => "explore",
+            Stage::Info => "info",
+            Stage::Toc => "toc",
+            Stage::Content => "content",
+            Stage::Login => "login",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// 规则执行失败时的诊断信息，由内向外逐层补充
+#[derive(Debug)]
+pub struct RuleDiagnostic {
+    /// 书源名称
+    pub book_source: Option<String>,
+    pub stage: Option<Stage>,
+    /// 规则字段，例如 ruleToc.chapterUrl
+    pub field: Option<String>,
+    /// 出错的规则步骤，例如 @css:a@href
+    pub step: Option<String>,
+    /// 该步骤输入内容的片段
+    pub input: Option<String>,
+    /// 原始错误
+    pub cause: ParseError,
+}
+
+impl Display for RuleDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(book_source) = &self.book_source {
+            writeln!(f, "书源: {}", book_source)?;
+        }
+        if let Some(stage) = &self.stage {
+            writeln!(f, "阶段: {}", stage)?;
+        }
+        if let Some(field) = &self.field {
+            writeln!(f, "字段: {}", field)?;
+        }
+        if let Some(step) = &self.step {
+            writeln!(f, "步骤: {}", step)?;
+        }
+        if let Some(input) = &self.input {
+            writeln!(f, "输入: {}", input)?;
+        }
+        write!(f, "原因: {}", self.cause)
+    }
+}
+
+impl std::error::Error for RuleDiagnostic {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.cause)
+    }
+}
+
+/// 输入片段的最大字符数
+const SNIPPET_LENGTH: usize = 120;
+
+/// 截取输入内容的片段，合并空白字符
+pub fn snippet(input: &str) -> String {
+    let mut res = String::new();
+    for (index, word) in input.split_whitespace().enumerate() {
+        if index > 0 {
+            res.push(' ');
+        }
+        res.push_str(word);
+        if res.chars().count() > SNIPPET_LENGTH {
+            return format!("{}…", res.chars().take(SNIPPET_LENGTH).collect::<String>());
+        }
+    }
+    res
+}
+
+impl ParseError {
+    /// 规则诊断信息，不是规则错误时返回 None
+    pub fn diagnostic(&self) -> Option<&RuleDiagnostic> {
+        match self {
+            ParseError::Rule(diagnostic) => Some(diagnostic),
+            _ => None,
+        }
+    }
+
+    fn map_diagnostic(self, f: impl FnOnce(&mut RuleDiagnostic)) -> Self {
+        let mut diagnostic = match self {
+            ParseError::Rule(diagnostic) => diagnostic,
+            cause => Box::new(RuleDiagnostic {
+                book_source: None,
+                stage: None,
+                field: None,
+                step: None,
+                input: None,
+                cause,
+            }),
+        };
+        f(&mut diagnostic);
+        ParseError::Rule(diagnostic)
+    }
+
+    /// 记录出错的规则步骤和输入，已经记录过时保留最内层的步骤
+    pub fn with_step(self, step: impl Display, input: &str) -> Self {
+        self.map_diagnostic(|diagnostic| {
+            if diagnostic.step.is_none() {
+                diagnostic.step = Some(step.to_string());
+                diagnostic.input = Some(snippet(input));
+            }
+        })
+    }
+
+    /// 记录规则字段，已经记录过时保留最内层的字段
+    pub fn with_field(self, field: &str) -> Self {
+        self.map_diagnostic(|diagnostic| {
+            if diagnostic.field.is_none() {
+                diagnostic.field = Some(field.to_string());
+            }
+        })
+    }
+
+    /// 记录书源和阶段，`rule` 不为空时作为字段的前缀，例如 ruleToc + chapterUrl
+    /// 已经是完整路径的字段（例如 ruleBookInfo.tocUrl）不加前缀
+    pub fn with_stage(self, book_source: &str, stage: Stage, rule: Option<&str>) -> Self {
+        self.map_diagnostic(|diagnostic| {
+            if diagnostic.stage.is_some() {
+                return;
+            }
+            diagnostic.book_source = Some(book_source.to_string());
+            diagnostic.stage = Some(stage);
+            if let (Some(rule), Some(field)) = (rule, &diagnostic.field) {
+                if !field.contains('.') {
+                    diagnostic.field = Some(format!("{}.{}", rule, field));
+                }
+            }
+        })
+    }
+}
+
+/// 为 [Result] 补充规则诊断信息，见 [ParseError::with_step] 等
+pub trait RuleContext<T> {
+    fn step(self, step: impl Display, input: &str) -> Result<T>;
+    fn field(self, field: &str) -> Result<T>;
+    fn stage(self, book_source: &str, stage: Stage, rule: Option<&str>) -> Result<T>;
+}
+
+impl<T> RuleContext<T> for Result<T> {
+    fn step(self, step: impl Display, input: &str) -> Result<T> {
+        self.map_err(|e| e.with_step(step, input))
+    }
+
+    fn field(self, field: &str) -> Result<T> {
+        self.map_err(|e| e.with_field(field))
+    }
+
+    fn stage(self, book_source: &str, stage: Stage, rule: Option<&str>) -> Result<T> {
+        self.map_err(|e| e.with_stage(book_source, stage, rule))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    #[test]
+    fn test_rule_diagnostic() {
+        let res: Result<()> = Err(anyhow!("selector is not valid").into());
+        let err = res
+            .step("@css:a@href", "<ul>\n  <li>第一章</li>\n</ul>")
+            .step("@css:ul", "")
+            .field("chapterUrl")
+            .stage("测试书源", Stage::Toc, Some("ruleToc"))
+            .stage("其他", Stage::Info, None)
+            .unwrap_err();
+
+        let diagnostic = err.diagnostic().unwrap();
+        assert_eq!(diagnostic.field.as_deref(), Some("ruleToc.chapterUrl"));
+        assert_eq!(diagnostic.step.as_deref(), Some("@css:a@href"));
+        assert_eq!(
+            err.to_string(),
+            "书源: 测试书源\n阶段: toc\n字段: ruleToc.chapterUrl\n步骤: @css:a@href\n输入: <ul> <li>第一章</li> </ul>\n原因: selector is not valid"
+        );
+
+        let long = "a".repeat(200);
+        assert_eq!(snippet(&long).chars().count(), SNIPPET_LENGTH + 1);
+    }
+}
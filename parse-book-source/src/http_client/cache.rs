@@ -0,0 +1,117 @@
+//! 保存在磁盘上的响应缓存，每个请求一个 JSON 文件，文件名和 cassette 相同
+//!
+//! 只缓存状态码为 200 的响应，缓存过期之后使用 `ETag` 和 `Last-Modified` 重新验证
+
+use super::{
+    cassette::{entry_name, RecordedResponse},
+    transport::{HttpRequest, HttpResponse},
+};
+use crate::Result;
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+use std::{
+    path::PathBuf,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// 临时文件的序号，同一进程中并发保存时使用不同的临时文件
+static TEMP_SEQ: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CacheEntry {
+    /// 保存或者最近一次验证的时间，unix 时间戳（秒）
+    stored_at: u64,
+    response: RecordedResponse,
+}
+
+/// 缓存的响应
+#[derive(Debug, Clone)]
+pub struct CachedResponse {
+    pub response: HttpResponse,
+    /// 距离保存或者最近一次验证过去的时间
+    pub age: Duration,
+}
+
+impl CachedResponse {
+    /// 重新验证缓存时需要添加的条件请求头
+    pub fn conditional_headers(&self) -> Vec<(String, String)> {
+        let mut headers = vec![];
+        if let Some(etag) = self.response.header("etag") {
+            headers.push(("if-none-match".into(), etag.to_string()));
+        }
+        if let Some(last_modified) = self.response.header("last-modified") {
+            headers.push(("if-modified-since".into(), last_modified.to_string()));
+        }
+        headers
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// 缓存目录
+#[derive(Debug, Clone)]
+pub struct HttpCache {
+    pub dir: PathBuf,
+}
+
+impl HttpCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn path(&self, request: &HttpRequest) -> PathBuf {
+        self.dir.join(entry_name(request))
+    }
+
+    /// 读取请求的缓存，没有缓存或者缓存文件损坏时返回 None
+    pub async fn load(&self, request: &HttpRequest) -> Option<CachedResponse> {
+        let content = tokio::fs::read(self.path(request)).await.ok()?;
+        let entry: CacheEntry = serde_json::from_slice(&content).ok()?;
+        Some(CachedResponse {
+            age: Duration::from_secs(now().saturating_sub(entry.stored_at)),
+            response: entry.response.try_into().ok()?,
+        })
+    }
+
+    /// 保存响应，保存时间为当前时间，重新验证成功时也用它刷新保存时间
+    pub async fn store(&self, request: &HttpRequest, response: &HttpResponse) -> Result<()> {
+        let entry = CacheEntry {
+            stored_at: now(),
+            response: response.clone().into(),
+        };
+        tokio::fs::create_dir_all(&self.dir)
+            .await
+            .map_err(|e| anyhow!("create {} failed: {}", self.dir.display(), e))?;
+
+        // 先写入临时文件再替换，避免并发请求读到写了一半的文件
+        let path = self.path(request);
+        let temp = path.with_extension(format!(
+            "{}-{}.tmp",
+            std::process::id(),
+            TEMP_SEQ.fetch_add(1, Ordering::Relaxed)
+        ));
+        tokio::fs::write(&temp, serde_json::to_vec(&entry)?)
+            .await
+            .map_err(|e| anyhow!("write {} failed: {}", temp.display(), e))?;
+        tokio::fs::rename(&temp, &path)
+            .await
+            .map_err(|e| anyhow!("write {} failed: {}", path.display(), e))?;
+        Ok(())
+    }
+
+    /// 删除所有缓存
+    pub fn clear(&self) -> Result<()> {
+        if self.dir.exists() {
+            std::fs::remove_dir_all(&self.dir)
+                .map_err(|e| anyhow!("remove {} failed: {}", self.dir.display(), e))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,209 @@
+//! 请求录制和回放，录制的结果保存在 cassette 目录中，每个请求一个 JSON 文件
+//!
+//! 文件名由请求方法、链接和请求体计算得出，回放时按同样的方式查找，
+//! 可以用于书源的离线回归测试，也可以附在问题反馈中复现问题
+
+use super::transport::{HttpRequest, HttpResponse, Transport};
+use crate::Result;
+use anyhow::anyhow;
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Entry {
+    request: HttpRequest,
+    response: RecordedResponse,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct RecordedResponse {
+    url: String,
+    status: u16,
+    #[serde(default)]
+    headers: Vec<(String, String)>,
+    /// 响应内容是 utf-8 文本时直接保存，方便查看和修改
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    body: Option<String>,
+    /// 其他编码的响应内容使用 base64 保存
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    body_base64: Option<String>,
+}
+
+impl From<HttpResponse> for RecordedResponse {
+    fn from(res: HttpResponse) -> Self {
+        let (body, body_base64) = match String::from_utf8(res.body) {
+            Ok(body) => (Some(body), None),
+            Err(e) => (None, Some(STANDARD.encode(e.into_bytes()))),
+        };
+        Self {
+            url: res.url,
+            status: res.status,
+            headers: res.headers,
+            body,
+            body_base64,
+        }
+    }
+}
+
+impl TryFrom<RecordedResponse> for HttpResponse {
+    type Error = crate::ParseError;
+
+    fn try_from(res: RecordedResponse) -> Result<Self> {
+        let body = match (res.body, res.body_base64) {
+            (Some(body), _) => body.into_bytes(),
+            (None, Some(body)) => STANDARD.decode(body).map_err(|e| anyhow!(e))?,
+            (None, None) => vec![],
+        };
+        Ok(Self {
+            url: res.url,
+            status: res.status,
+            headers: res.headers,
+            body,
+        })
+    }
+}
+
+/// 请求对应的文件名，由请求方法、链接和请求体的 FNV-1a 哈希得出，保证不同版本之间文件名不变
+pub(super) fn entry_name(request: &HttpRequest) -> String {
+    let key = format!(
+        "{} {}\n{}",
+        request.method.to_uppercase(),
+        request.url,
+        request.body.as_deref().unwrap_or_default()
+    );
+    let hash = key.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+    format!("{:016x}.json", hash)
+}
+
+fn entry_path(dir: &Path, request: &HttpRequest) -> PathBuf {
+    dir.join(entry_name(request))
+}
+
+/// 录制请求，把内部传输层的响应保存到 cassette 目录
+#[derive(Debug, Clone)]
+pub struct Recorder {
+    pub inner: Arc<dyn Transport>,
+    pub dir: PathBuf,
+}
+
+impl Recorder {
+    pub fn new(inner: Arc<dyn Transport>, dir: impl Into<PathBuf>) -> Self {
+        Self {
+            inner,
+            dir: dir.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl Transport for Recorder {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse> {
+        let response = self.inner.send(request.clone()).await?;
+
+        let path = entry_path(&self.dir, &request);
+        let entry = Entry {
+            request,
+            response: response.clone().into(),
+        };
+        tokio::fs::create_dir_all(&self.dir)
+            .await
+            .map_err(|e| anyhow!("create {} failed: {}", self.dir.display(), e))?;
+        tokio::fs::write(&path, serde_json::to_vec_pretty(&entry)?)
+            .await
+            .map_err(|e| anyhow!("write {} failed: {}", path.display(), e))?;
+
+        Ok(response)
+    }
+}
+
+/// 回放 cassette 目录中录制的响应，没有录制的请求直接报错，不会访问网络
+#[derive(Debug, Clone)]
+pub struct Replayer {
+    pub dir: PathBuf,
+}
+
+impl Replayer {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+}
+
+#[async_trait]
+impl Transport for Replayer {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse> {
+        let path = entry_path(&self.dir, &request);
+        let content = tokio::fs::read(&path).await.map_err(|_| {
+            anyhow!(
+                "cassette 中没有录制该请求: {} {}",
+                request.method,
+                request.url
+            )
+        })?;
+        let entry: Entry = serde_json::from_slice(&content)?;
+        entry.response.try_into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        utils::test_server::{serve, TestResponse},
+        HttpClient, HttpConfig, RequestOptions,
+    };
+
+    #[tokio::test]
+    async fn test_record_and_replay() {
+        let dir = std::env::temp_dir().join(format!("cassette-test-{}", std::process::id()));
+        let base_url = serve(|req| {
+            let body = format!("{} {}", req.method, req.path);
+            let (body, _, _) = encoding_rs::GBK.encode(&body);
+            TestResponse::ok(body.into_owned())
+        });
+
+        let client = HttpClient::new(&base_url, &HttpConfig::default()).unwrap();
+        let client = client
+            .clone()
+            .with_transport(Arc::new(Recorder::new(client.transport.clone(), &dir)));
+        assert_eq!(client.get_text("/book/1").await.unwrap(), "GET /book/1");
+        let options = RequestOptions {
+            method: Some("POST".into()),
+            ..Default::default()
+        };
+        client
+            .request_text("/search", &options, Some("q=斗破".into()))
+            .await
+            .unwrap();
+
+        let client = HttpClient::new(&base_url, &HttpConfig::default())
+            .unwrap()
+            .with_transport(Arc::new(Replayer::new(&dir)));
+        assert_eq!(client.get_text("/book/1").await.unwrap(), "GET /book/1");
+        assert_eq!(
+            client
+                .request_text("/search", &options, Some("q=斗破".into()))
+                .await
+                .unwrap(),
+            "POST /search"
+        );
+
+        let err = client.get_text("/book/2").await.unwrap_err();
+        assert!(err.to_string().contains("/book/2"));
+        // 不同的请求体不会命中
+        assert!(client
+            .request_text("/search", &options, Some("q=诡秘".into()))
+            .await
+            .is_err());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
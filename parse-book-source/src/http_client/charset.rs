@@ -0,0 +1,121 @@
+//! 响应内容的字符集检测
+//!
+//! 按以下顺序确定字符集：
+//! 1. 请求选项或书源配置中指定的字符集
+//! 2. BOM
+//! 3. `Content-Type` 响应头中的 charset
+//! 4. 页面开头的 `<meta charset>` / `<meta http-equiv="Content-Type">` 或 XML 声明
+//! 5. 内容是有效的 UTF-8 时使用 UTF-8，否则使用 GB18030
+
+use encoding_rs::{Encoding, GB18030, UTF_8};
+use regex::bytes::Regex;
+use std::sync::LazyLock;
+
+/// 只在页面开头查找 meta 标签
+const SNIFF_LENGTH: usize = 4096;
+
+static META_CHARSET: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?i)<meta[^>]*?charset\s*=\s*["']?\s*([\w.:-]+)|<\?xml[^>]*?encoding\s*=\s*["']([\w.:-]+)"#)
+        .unwrap()
+});
+
+/// `Content-Type` 中的 charset
+fn charset_from_content_type(content_type: &str) -> Option<&'static Encoding> {
+    content_type.split(';').find_map(|part| {
+        let (key, value) = part.split_once('=')?;
+        if !key.trim().eq_ignore_ascii_case("charset") {
+            return None;
+        }
+        Encoding::for_label(value.trim().trim_matches(['"', '\'']).as_bytes())
+    })
+}
+
+/// 页面开头 meta 标签或 XML 声明中的字符集
+fn charset_from_meta(bytes: &[u8]) -> Option<&'static Encoding> {
+    let head = &bytes[..bytes.len().min(SNIFF_LENGTH)];
+    let captures = META_CHARSET.captures(head)?;
+    let label = captures.get(1).or(captures.get(2))?;
+    let encoding = Encoding::for_label(label.as_bytes())?;
+    // 能读到 ASCII 的 meta 标签说明不是 UTF-16
+    if encoding == encoding_rs::UTF_16BE || encoding == encoding_rs::UTF_16LE {
+        return Some(UTF_8);
+    }
+    Some(encoding)
+}
+
+/// 检测字符集，`content_type` 为响应头
+pub fn sniff(bytes: &[u8], content_type: Option<&str>) -> &'static Encoding {
+    if let Some((encoding, _)) = Encoding::for_bom(bytes) {
+        return encoding;
+    }
+
+    if let Some(encoding) = content_type.and_then(charset_from_content_type) {
+        return encoding;
+    }
+
+    if let Some(encoding) = charset_from_meta(bytes) {
+        return encoding;
+    }
+
+    if std::str::from_utf8(bytes).is_ok() {
+        UTF_8
+    } else {
+        GB18030
+    }
+}
+
+/// 解码响应内容，指定了字符集时不再检测
+pub fn decode(
+    bytes: &[u8],
+    content_type: Option<&str>,
+    charset: Option<&'static Encoding>,
+) -> String {
+    let encoding = charset.unwrap_or_else(|| sniff(bytes, content_type));
+    // decode 会处理 BOM
+    encoding.decode(bytes).0.into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use encoding_rs::{GBK, UTF_16LE};
+
+    #[test]
+    fn test_sniff() {
+        let (gbk, _, _) = GBK.encode("<html><head><meta charset=\"gbk\"></head>斗破苍穹</html>");
+        assert_eq!(sniff(&gbk, Some("text/html")), GBK);
+        assert_eq!(
+            decode(&gbk, Some("text/html"), None),
+            "<html><head><meta charset=\"gbk\"></head>斗破苍穹</html>"
+        );
+
+        let (gbk, _, _) = GBK.encode(
+            r#"<meta http-equiv="Content-Type" content="text/html; charset=gb2312" />斗破"#,
+        );
+        assert_eq!(sniff(&gbk, None), GBK);
+
+        // 响应头优先于 meta 标签
+        assert_eq!(sniff(&gbk, Some("text/html; charset=UTF-8")), UTF_8);
+
+        let (xml, _, _) = GBK.encode(r#"<?xml version="1.0" encoding="GBK"?><a>斗破</a>"#);
+        assert_eq!(sniff(&xml, None), GBK);
+
+        // BOM 优先
+        assert_eq!(
+            sniff(b"\xFF\xFEa\x00", Some("text/html; charset=gbk")),
+            UTF_16LE
+        );
+        assert_eq!(decode("\u{FEFF}斗破".as_bytes(), None, None), "斗破");
+
+        // 没有声明时根据内容判断
+        let (gbk, _, _) = GBK.encode("斗破苍穹");
+        assert_eq!(sniff(&gbk, None), GB18030);
+        assert_eq!(sniff("斗破苍穹".as_bytes(), None), UTF_8);
+
+        // 指定的字符集不再检测
+        assert_eq!(
+            decode(&gbk, Some("text/html; charset=utf-8"), Some(GBK)),
+            "斗破苍穹"
+        );
+    }
+}
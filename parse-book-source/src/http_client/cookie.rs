@@ -0,0 +1,115 @@
+//! 持久化的 cookie，保存在书源各自的文件中，退出之后登录状态不会丢失
+//!
+//! 同一个文件的 cookie 在进程中只加载一次，使用这个文件的所有解析器共享同一份，
+//! 避免各自保存时互相覆盖登录状态
+
+use crate::Result;
+use anyhow::anyhow;
+use cookie_store::{serde::json, CookieStore};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, LazyLock, Mutex, MutexGuard,
+    },
+};
+
+pub use reqwest_cookie_store::CookieStoreMutex;
+
+/// 临时文件的序号，同一进程中并发保存时使用不同的临时文件
+static TEMP_SEQ: AtomicUsize = AtomicUsize::new(0);
+
+/// 按文件共享的 cookie
+static SHARED: LazyLock<Mutex<HashMap<PathBuf, Arc<CookieStoreMutex>>>> =
+    LazyLock::new(Default::default);
+
+/// 每个文件最近一次保存的内容，没有变化时不再写入
+static SAVED: LazyLock<Mutex<HashMap<PathBuf, Vec<u8>>>> = LazyLock::new(Default::default);
+
+/// 加载 cookie 文件，文件不存在时返回空的 cookie，已过期的 cookie 会被忽略
+pub fn load(path: &Path) -> Result<CookieStore> {
+    match File::open(path) {
+        Ok(file) => Ok(json::load(BufReader::new(file))
+            .map_err(|e| anyhow!("load cookies from {} failed: {}", path.display(), e))?),
+        Err(_) => Ok(CookieStore::default()),
+    }
+}
+
+fn serialize(cookies: &CookieStore) -> Result<Vec<u8>> {
+    let mut content = vec![];
+    json::save_incl_expired_and_nonpersistent(cookies, &mut content)
+        .map_err(|e| anyhow!("save cookies failed: {}", e))?;
+    Ok(content)
+}
+
+/// 获取文件对应的共享 cookie，第一次使用时从文件加载
+pub fn shared(path: &Path) -> Result<Arc<CookieStoreMutex>> {
+    let mut shared = SHARED
+        .lock()
+        .map_err(|_| anyhow!("cookie stores are poisoned"))?;
+    if let Some(cookies) = shared.get(path) {
+        return Ok(cookies.clone());
+    }
+
+    let store = load(path)?;
+    let content = serialize(&store)?;
+    let cookies = Arc::new(CookieStoreMutex::new(store));
+    shared.insert(path.to_path_buf(), cookies.clone());
+    if let Ok(mut saved) = SAVED.lock() {
+        saved.insert(path.to_path_buf(), content);
+    }
+    Ok(cookies)
+}
+
+/// 保存 cookie，包括会话 cookie，内容和上次保存的相同时不写入文件，
+/// 先写入临时文件再替换，避免并发请求写坏文件
+pub fn save(cookies: &CookieStoreMutex, path: &Path) -> Result<()> {
+    let content = serialize(&*lock(cookies)?)?;
+    let mut saved = SAVED
+        .lock()
+        .map_err(|_| anyhow!("cookie files are poisoned"))?;
+    if saved.get(path) == Some(&content) {
+        return Ok(());
+    }
+
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let temp = path.with_extension(format!(
+        "{}-{}.tmp",
+        std::process::id(),
+        TEMP_SEQ.fetch_add(1, Ordering::Relaxed)
+    ));
+    std::fs::write(&temp, &content)?;
+    std::fs::rename(&temp, path)?;
+    saved.insert(path.to_path_buf(), content);
+    Ok(())
+}
+
+/// 清空 cookie 并删除文件
+pub fn clear(cookies: &CookieStoreMutex, path: &Path) -> Result<()> {
+    let mut store = lock(cookies)?;
+    store.clear();
+    let content = serialize(&store)?;
+    if path.exists() {
+        std::fs::remove_file(path)?;
+    }
+    if let Ok(mut saved) = SAVED.lock() {
+        saved.insert(path.to_path_buf(), content);
+    }
+    Ok(())
+}
+
+pub fn lock(cookies: &CookieStoreMutex) -> Result<MutexGuard<'_, CookieStore>> {
+    Ok(cookies
+        .lock()
+        .map_err(|_| anyhow!("cookie store is poisoned"))?)
+}
+
+/// 新建空的 cookie
+pub fn new_store() -> Arc<CookieStoreMutex> {
+    Arc::new(CookieStoreMutex::default())
+}
@@ -1,81 +1,599 @@
-use crate::{HttpConfig, Result};
-use anyhow::anyhow;
-use rate_limiter::TokenBucket;
-use reqwest::{
-    header::{HeaderMap, HeaderName, HeaderValue},
-    Body, Client, ClientBuilder,
+use crate::{
+    analyzer::codec::get_encoding, utils::resolve_url, CacheTtl, HttpConfig, RateLimit,
+    RequestOptions, Result, RetryPolicy, Stage,
 };
-use std::time::Duration;
+use encoding_rs::Encoding;
+use reqwest::Url;
+use std::{path::PathBuf, sync::Arc};
+pub mod cache;
+pub mod cassette;
+pub mod charset;
+pub mod cookie;
 pub mod rate_limiter;
+pub mod retry;
+pub mod transport;
+pub use cache::HttpCache;
+pub use cassette::{Recorder, Replayer};
+pub use cookie::CookieStoreMutex;
+pub use transport::{HttpRequest, HttpResponse, ReqwestTransport, Transport};
+
+/// 请求到的页面
+#[derive(Debug, Clone, Default)]
+pub struct Page {
+    /// 重定向之后的实际链接，页面中的相对链接以它为基准解析
+    pub url: String,
+    pub text: String,
+    /// 响应状态码，读取本地文件时为 200
+    pub status: u16,
+}
 
 #[derive(Debug, Clone)]
 pub struct HttpClient {
-    pub client: Client,
+    pub transport: Arc<dyn Transport>,
     pub base_url: String,
-    pub rate_limiter: Option<TokenBucket>,
+    /// 请求速率限制，同一个域名的请求共享令牌桶，见 [rate_limiter::host_bucket]
+    pub rate_limit: Option<RateLimit>,
+    /// 书源指定的响应字符集
+    pub charset: Option<&'static Encoding>,
+    pub retry: Option<RetryPolicy>,
+    /// 所有请求共享的 cookie，克隆之后仍然是同一份
+    pub cookies: Arc<CookieStoreMutex>,
+    /// 保存 cookie 的文件，设置后请求之后 cookie 有变化时保存
+    pub cookie_file: Option<PathBuf>,
+    /// 响应缓存，设置了缓存目录才会缓存，见 [HttpClient::with_cache_dir]
+    pub cache: Option<HttpCache>,
+    pub cache_ttl: CacheTtl,
+    /// 当前请求所在的阶段，决定缓存的有效期，为空时不缓存
+    pub stage: Option<Stage>,
+    /// 创建传输层使用的配置，更换 cookie 时重新创建传输层
+    pub config: HttpConfig,
 }
 
 impl HttpClient {
     pub fn new(base_url: &str, config: &HttpConfig) -> Result<Self> {
-        let mut client = ClientBuilder::new().cookie_store(true);
-
-        if let Some(header) = &config.header {
-            let mut headers = HeaderMap::new();
-
-            for (k, v) in header {
-                headers.insert(
-                    HeaderName::try_from(k)
-                        .map_err(|e| anyhow!("header name is not valid: {}", e))?,
-                    HeaderValue::from_str(v)
-                        .map_err(|e| anyhow!("header value is not valid: {}", e))?,
-                );
+        let cookies = cookie::new_store();
+        Ok(Self {
+            transport: Arc::new(ReqwestTransport::with_cookies(config, cookies.clone())?),
+            cookies,
+            cookie_file: None,
+            base_url: base_url.to_string(),
+            charset: config.charset.as_deref().map(get_encoding).transpose()?,
+            retry: config.retry.clone(),
+            rate_limit: config.rate_limit.clone(),
+            cache: None,
+            cache_ttl: config.cache.clone().unwrap_or_default(),
+            stage: None,
+            config: config.clone(),
+        })
+    }
+
+    /// 替换传输层，例如使用 [Recorder] 录制请求或者 [Replayer] 回放请求
+    pub fn with_transport(mut self, transport: Arc<dyn Transport>) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    /// 使用文件对应的共享 cookie，之后的请求会把 cookie 保存回这个文件，见 [cookie::shared]
+    ///
+    /// 会重新创建传输层，需要在 [HttpClient::with_transport] 之前调用
+    pub fn with_cookie_file(mut self, path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        self.cookies = cookie::shared(&path)?;
+        self.transport = Arc::new(ReqwestTransport::with_cookies(
+            &self.config,
+            self.cookies.clone(),
+        )?);
+        self.cookie_file = Some(path);
+        Ok(self)
+    }
+
+    /// 把响应缓存到指定目录，各个阶段的有效期见 [CacheTtl]
+    pub fn with_cache_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.cache = Some(HttpCache::new(dir));
+        self
+    }
+
+    /// 保存 cookie，没有设置 cookie 文件时什么也不做
+    pub fn save_cookies(&self) -> Result<()> {
+        match &self.cookie_file {
+            Some(path) => cookie::save(&self.cookies, path),
+            None => Ok(()),
+        }
+    }
+
+    /// 清空 cookie 并删除 cookie 文件，即退出登录
+    pub fn clear_cookies(&self) -> Result<()> {
+        match &self.cookie_file {
+            Some(path) => cookie::clear(&self.cookies, path),
+            None => {
+                cookie::lock(&self.cookies)?.clear();
+                Ok(())
             }
-            client = client.default_headers(headers);
         }
+    }
 
-        if let Some(timeout) = config.timeout {
-            client = client.timeout(Duration::from_millis(timeout));
+    /// 删除所有缓存的响应，没有设置缓存目录时什么也不做
+    pub fn clear_cache(&self) -> Result<()> {
+        match &self.cache {
+            Some(cache) => cache.clear(),
+            None => Ok(()),
         }
+    }
 
-        Ok(Self {
-            client: client.build()?,
-            base_url: base_url.to_string(),
-            rate_limiter: config.rate_limit.as_ref().map(|rate_limit| {
-                TokenBucket::new(
-                    rate_limit.max_count as usize,
-                    Duration::from_secs_f64(rate_limit.fill_duration),
-                )
-            }),
-        })
+    /// 未过期的 cookie 数量
+    pub fn cookie_count(&self) -> usize {
+        cookie::lock(&self.cookies)
+            .map(|cookies| cookies.iter_unexpired().count())
+            .unwrap_or_default()
     }
 
     fn url_with_base(&self, url: &str) -> String {
-        if url.starts_with("http") {
-            url.to_string()
-        } else if url.starts_with('/') {
-            format!("{}{}", self.base_url, url)
-        } else {
-            format!("{}/{}", self.base_url, url)
+        resolve_url(&self.base_url, url)
+    }
+
+    /// 发送请求，`file://` 链接直接读取本地文件，不经过传输层
+    /// 当前阶段可以缓存时，优先使用有效期内的缓存，过期的缓存发送条件请求重新验证
+    pub async fn send(&self, mut request: HttpRequest) -> Result<HttpResponse> {
+        request.url = self.url_with_base(&request.url);
+
+        if request.url.starts_with("file://") {
+            return transport::read_file(&request.url).await;
+        }
+
+        let cache = self
+            .stage
+            .and_then(|stage| self.cache_ttl.for_stage(stage))
+            .and_then(|ttl| Some((self.cache.as_ref()?, ttl)));
+        let Some((cache, ttl)) = cache else {
+            return self.send_with_retry(request).await;
+        };
+
+        let cached = cache.load(&request).await;
+        let mut conditional = request.clone();
+        if let Some(cached) = &cached {
+            if cached.age < ttl {
+                return Ok(cached.response.clone());
+            }
+            conditional.headers.extend(cached.conditional_headers());
+        }
+
+        let res = self.send_with_retry(conditional).await?;
+        match cached {
+            Some(cached) if res.status == 304 => {
+                cache.store(&request, &cached.response).await.ok();
+                Ok(cached.response)
+            }
+            _ => {
+                if res.status == 200 {
+                    // 缓存写入失败不影响请求结果
+                    cache.store(&request, &res).await.ok();
+                }
+                Ok(res)
+            }
+        }
+    }
+
+    /// 设置了重试策略时，连接失败、超时和需要重试的状态码会按策略重试
+    async fn send_with_retry(&self, request: HttpRequest) -> Result<HttpResponse> {
+        let Some(retry) = self
+            .retry
+            .as_ref()
+            .filter(|retry| retry.can_retry(&request))
+        else {
+            return self.send_once(request).await;
+        };
+
+        let mut attempt = 1;
+        loop {
+            let res = self.send_once(request.clone()).await;
+            let retryable = match &res {
+                Ok(res) => retry.should_retry(res),
+                Err(e) => retry.should_retry_error(e),
+            };
+            if !retryable || attempt >= retry.max_attempts {
+                return res;
+            }
+
+            tokio::time::sleep(retry.delay_for(attempt, res.as_ref().ok())).await;
+            attempt += 1;
+        }
+    }
+
+    /// 发送一次请求，先从域名的令牌桶中获取令牌，再获取全局的并发许可
+    async fn send_once(&self, request: HttpRequest) -> Result<HttpResponse> {
+        let host = Url::parse(&request.url)
+            .ok()
+            .and_then(|url| url.host_str().map(|host| host.to_string()));
+        if let Some(host) = host {
+            rate_limiter::host_bucket(&host, self.rate_limit.as_ref())
+                .acquire()
+                .await;
+        }
+
+        let permit = rate_limiter::acquire_concurrency().await;
+        let res = self.transport.send(request).await;
+        drop(permit);
+        // 保存失败不影响请求结果，登录时会再次保存并返回错误
+        if let Err(e) = self.save_cookies() {
+            log::warn!("save cookies failed: {}", e);
+        }
+        res
+    }
+
+    pub async fn get(&self, url: &str) -> Result<HttpResponse> {
+        self.send(HttpRequest::get(url)).await
+    }
+
+    /// 请求链接并返回文本
+    pub async fn get_text(&self, url: &str) -> Result<String> {
+        Ok(self.get_page(url).await?.text)
+    }
+
+    /// 请求链接并返回页面
+    pub async fn get_page(&self, url: &str) -> Result<Page> {
+        let res = self.get(url).await?;
+        Ok(Self::read_page(res, self.charset))
+    }
+
+    /// 读取响应内容，`charset` 为空时自动检测字符集，见 [charset]
+    fn read_page(res: HttpResponse, charset: Option<&'static Encoding>) -> Page {
+        let text = charset::decode(&res.body, res.header("content-type"), charset);
+        Page {
+            url: res.url,
+            text,
+            status: res.status,
         }
     }
 
-    pub async fn get(&self, url: &str) -> Result<reqwest::Response> {
-        if let Some(rate_limiter) = &self.rate_limiter {
-            rate_limiter.acquire().await;
+    pub async fn post<T: Into<String>>(&self, url: &str, body: T) -> Result<HttpResponse> {
+        self.send(HttpRequest {
+            method: "POST".into(),
+            url: url.into(),
+            body: Some(body.into()),
+            ..Default::default()
+        })
+        .await
+    }
+
+    /// 按请求选项发送请求，`body` 为渲染后的请求体，返回按选项字符集解码的响应内容
+    pub async fn request_text(
+        &self,
+        url: &str,
+        options: &RequestOptions,
+        body: Option<String>,
+    ) -> Result<String> {
+        Ok(self.request_page(url, options, body).await?.text)
+    }
+
+    /// 和 [HttpClient::request_text] 相同，同时返回重定向之后的实际链接
+    pub async fn request_page(
+        &self,
+        url: &str,
+        options: &RequestOptions,
+        body: Option<String>,
+    ) -> Result<Page> {
+        let method = options.method.as_deref().unwrap_or("GET").to_uppercase();
+
+        let mut headers = options
+            .headers
+            .iter()
+            .flatten()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect::<Vec<_>>();
+
+        if let Some(body) = &body {
+            let content_type = options.content_type.clone().unwrap_or_else(|| {
+                if body.trim_start().starts_with(['{', '[']) {
+                    "application/json".to_string()
+                } else {
+                    "application/x-www-form-urlencoded".to_string()
+                }
+            });
+            headers.push(("content-type".into(), content_type));
         }
 
-        let url = self.url_with_base(url);
+        let res = self
+            .send(HttpRequest {
+                method,
+                url: url.into(),
+                headers,
+                body,
+            })
+            .await?;
+
+        // 请求选项的字符集优先于书源的字符集
+        let charset = match &options.charset {
+            Some(charset) => Some(get_encoding(charset)?),
+            None => self.charset,
+        };
+        Ok(Self::read_page(res, charset))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_server::{serve, TestResponse};
+    use std::collections::HashMap;
+
+    #[tokio::test]
+    async fn test_request_text() {
+        let base_url = serve(|req| {
+            let body = format!(
+                "{} {} {} {} {}",
+                req.method,
+                req.path,
+                req.headers.get("content-type").cloned().unwrap_or_default(),
+                req.headers.get("x-token").cloned().unwrap_or_default(),
+                String::from_utf8_lossy(&req.body)
+            );
+            // 响应使用 gbk 编码
+            let (body, _, _) = encoding_rs::GBK.encode(&body);
+            TestResponse::ok(body.into_owned())
+        });
+        let client = HttpClient::new(&base_url, &HttpConfig::default()).unwrap();
+
+        let options = RequestOptions {
+            method: Some("post".into()),
+            headers: Some(HashMap::from([("x-token".into(), "abc".into())])),
+            charset: Some("gbk".into()),
+            ..Default::default()
+        };
+        let res = client
+            .request_text("/search", &options, Some("q=%B6%B7%C6%C6".into()))
+            .await
+            .unwrap();
+        assert_eq!(
+            res,
+            "POST /search application/x-www-form-urlencoded abc q=%B6%B7%C6%C6"
+        );
+
+        let res = client
+            .request_text("/list", &RequestOptions::default(), None)
+            .await
+            .unwrap();
+        assert_eq!(res.trim(), "GET /list");
+    }
+
+    #[tokio::test]
+    async fn test_get_text_charset() {
+        let base_url = serve(|req| {
+            let body = match req.path.as_str() {
+                "/meta" => r#"<html><head><meta charset="gbk"></head><body>斗破苍穹</body></html>"#,
+                _ => "斗破苍穹",
+            };
+            let (body, _, _) = encoding_rs::GBK.encode(body);
+            TestResponse {
+                status: 200,
+                headers: vec![("content-type".into(), "text/html".into())],
+                body: body.into_owned(),
+            }
+        });
+
+        let client = HttpClient::new(&base_url, &HttpConfig::default()).unwrap();
+        let res = client.get_text("/meta").await.unwrap();
+        assert!(res.contains("斗破苍穹"));
+
+        let config = HttpConfig {
+            charset: Some("gb18030".into()),
+            ..Default::default()
+        };
+        let client = HttpClient::new(&base_url, &config).unwrap();
+        assert_eq!(client.get_text("/plain").await.unwrap(), "斗破苍穹");
+
+        let config = HttpConfig {
+            charset: Some("unknown".into()),
+            ..Default::default()
+        };
+        assert!(HttpClient::new(&base_url, &config).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_get_text_file_url() {
+        let dir = std::env::temp_dir().join(format!("file-url-test-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("book")).unwrap();
+        let (body, _, _) = encoding_rs::GBK.encode(r#"<meta charset="gbk">斗破苍穹"#);
+        std::fs::write(dir.join("book/1.html"), body).unwrap();
+
+        let base_url = reqwest::Url::from_directory_path(&dir).unwrap().to_string();
+        let client = HttpClient::new(&base_url, &HttpConfig::default()).unwrap();
+        let page = client.get_page("book/1.html").await.unwrap();
+        assert!(page.text.contains("斗破苍穹"));
+        assert_eq!(page.url, format!("{}book/1.html", base_url));
+
+        assert!(client.get_text("book/2.html").await.is_err());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_cache() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let count = Arc::new(AtomicUsize::new(0));
+        let base_url = serve({
+            let count = count.clone();
+            move |req| {
+                count.fetch_add(1, Ordering::SeqCst);
+                if req.headers.get("if-none-match").map(|s| s.as_str()) == Some("\"v1\"") {
+                    return TestResponse {
+                        status: 304,
+                        headers: vec![],
+                        body: vec![],
+                    };
+                }
+                TestResponse {
+                    status: 200,
+                    headers: vec![("etag".into(), "\"v1\"".into())],
+                    body: req.path.clone().into_bytes(),
+                }
+            }
+        });
+        let dir = std::env::temp_dir().join(format!("http-cache-test-{}", std::process::id()));
+
+        let config = HttpConfig {
+            cache: Some(CacheTtl {
+                toc: Some(0),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let mut client = HttpClient::new(&base_url, &config)
+            .unwrap()
+            .with_cache_dir(&dir);
+
+        // 有效期内不访问网络
+        client.stage = Some(Stage::Content);
+        assert_eq!(client.get_text("/chapter/1").await.unwrap(), "/chapter/1");
+        assert_eq!(client.get_text("/chapter/1").await.unwrap(), "/chapter/1");
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+
+        // 过期之后发送条件请求，304 时使用缓存
+        client.stage = Some(Stage::Toc);
+        assert_eq!(client.get_text("/toc").await.unwrap(), "/toc");
+        assert_eq!(client.get_text("/toc").await.unwrap(), "/toc");
+        assert_eq!(count.load(Ordering::SeqCst), 3);
+
+        // 不缓存的阶段每次都请求
+        client.stage = Some(Stage::Search);
+        client.get_text("/chapter/1").await.unwrap();
+        client.stage = None;
+        client.get_text("/chapter/1").await.unwrap();
+        assert_eq!(count.load(Ordering::SeqCst), 5);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_retry() {
+        use std::sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        };
+
+        let count = Arc::new(AtomicUsize::new(0));
+        let base_url = serve({
+            let count = count.clone();
+            move |_| {
+                if count.fetch_add(1, Ordering::SeqCst) < 2 {
+                    TestResponse {
+                        status: 503,
+                        headers: vec![("retry-after".into(), "0".into())],
+                        body: vec![],
+                    }
+                } else {
+                    TestResponse::ok("斗破苍穹")
+                }
+            }
+        });
+
+        let config = HttpConfig {
+            retry: Some(RetryPolicy {
+                base_delay: 1,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let client = HttpClient::new(&base_url, &config).unwrap();
+        assert_eq!(client.get_text("/book/1").await.unwrap(), "斗破苍穹");
+        assert_eq!(count.load(Ordering::SeqCst), 3);
+
+        // 次数用完后返回最后一次的响应
+        count.store(0, Ordering::SeqCst);
+        let config = HttpConfig {
+            retry: Some(RetryPolicy {
+                max_attempts: 2,
+                base_delay: 1,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let client = HttpClient::new(&base_url, &config).unwrap();
+        assert_eq!(client.get("/book/1").await.unwrap().status, 503);
+        assert_eq!(count.load(Ordering::SeqCst), 2);
+
+        // 没有重试策略时不重试
+        count.store(0, Ordering::SeqCst);
+        let client = HttpClient::new(&base_url, &HttpConfig::default()).unwrap();
+        assert_eq!(client.get("/book/1").await.unwrap().status, 503);
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+
+        // POST 默认不重试，设置 nonIdempotent 之后才重试
+        count.store(0, Ordering::SeqCst);
+        let mut config = HttpConfig {
+            retry: Some(RetryPolicy {
+                base_delay: 1,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let client = HttpClient::new(&base_url, &config).unwrap();
+        assert_eq!(client.post("/login", "a=1").await.unwrap().status, 503);
+        assert_eq!(count.load(Ordering::SeqCst), 1);
 
-        Ok(self.client.get(url).send().await?)
+        count.store(0, Ordering::SeqCst);
+        config.retry.as_mut().unwrap().non_idempotent = true;
+        let client = HttpClient::new(&base_url, &config).unwrap();
+        assert_eq!(client.post("/login", "a=1").await.unwrap().status, 200);
+        assert_eq!(count.load(Ordering::SeqCst), 3);
     }
 
-    pub async fn post<T: Into<Body>>(&self, url: &str, body: T) -> Result<reqwest::Response> {
-        if let Some(rate_limiter) = &self.rate_limiter {
-            rate_limiter.acquire().await;
+    #[tokio::test]
+    async fn test_retry_error() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        /// 每次请求都返回指定错误的传输层
+        #[derive(Debug)]
+        struct FailTransport {
+            count: AtomicUsize,
+            kind: Option<std::io::ErrorKind>,
+        }
+
+        #[async_trait::async_trait]
+        impl Transport for FailTransport {
+            async fn send(&self, _: HttpRequest) -> Result<HttpResponse> {
+                self.count.fetch_add(1, Ordering::SeqCst);
+                Err(match self.kind {
+                    Some(kind) => std::io::Error::from(kind).into(),
+                    None => anyhow::anyhow!("certificate is not valid").into(),
+                })
+            }
         }
 
-        let url = self.url_with_base(url);
+        let config = HttpConfig {
+            retry: Some(RetryPolicy {
+                base_delay: 1,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        for (kind, attempts) in [
+            (Some(std::io::ErrorKind::ConnectionRefused), 3),
+            (Some(std::io::ErrorKind::TimedOut), 3),
+            (Some(std::io::ErrorKind::InvalidData), 1),
+            (None, 1),
+        ] {
+            let transport = Arc::new(FailTransport {
+                count: AtomicUsize::new(0),
+                kind,
+            });
+            let client = HttpClient::new("http://localhost/", &config)
+                .unwrap()
+                .with_transport(transport.clone());
+            assert!(client.get("/book/1").await.is_err());
+            assert_eq!(
+                transport.count.load(Ordering::SeqCst),
+                attempts,
+                "{:?}",
+                kind
+            );
+        }
 
-        Ok(self.client.post(url).body(body).send().await?)
+        // 连接失败的 reqwest 错误同样重试
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+        let client = HttpClient::new(&format!("http://{}/", addr), &config).unwrap();
+        let err = client.get("/book/1").await.unwrap_err();
+        assert!(config.retry.as_ref().unwrap().should_retry_error(&err));
     }
 }
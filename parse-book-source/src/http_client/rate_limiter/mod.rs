@@ -1,2 +1,139 @@
 pub mod token_bucket;
 pub use token_bucket::*;
+
+use crate::RateLimit;
+use std::{
+    collections::HashMap,
+    sync::{LazyLock, Mutex, OnceLock},
+    time::Duration,
+};
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+/// 默认的全局最大并发请求数
+pub const DEFAULT_MAX_CONCURRENCY: usize = 16;
+
+/// 书源没有设置 `rateLimit` 时使用的限制，每 50 毫秒补充一个令牌，最多 20 个
+pub const DEFAULT_RATE_LIMIT: RateLimit = RateLimit {
+    max_count: 20,
+    fill_duration: 0.05,
+};
+
+/// 按域名共享的令牌桶，请求同一个网站的所有书源共用，
+/// 同时记录令牌桶是否来自书源设置的限制
+static HOST_BUCKETS: LazyLock<Mutex<HashMap<String, (TokenBucket, bool)>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+static CONCURRENCY: OnceLock<Semaphore> = OnceLock::new();
+
+/// 设置全局最大并发请求数，需要在第一次请求之前设置，已经设置过时返回 false
+pub fn set_max_concurrency(max: usize) -> bool {
+    CONCURRENCY.set(Semaphore::new(max.max(1))).is_ok()
+}
+
+/// 获取全局的并发许可，请求结束后释放
+pub async fn acquire_concurrency() -> SemaphorePermit<'static> {
+    CONCURRENCY
+        .get_or_init(|| Semaphore::new(DEFAULT_MAX_CONCURRENCY))
+        .acquire()
+        .await
+        .expect("concurrency semaphore is never closed")
+}
+
+/// 获取域名对应的令牌桶，同一个域名的所有书源共用一个令牌桶
+///
+/// 多个书源设置了不同的限制时使用最严格的，没有设置限制的书源使用已有的令牌桶，
+/// 域名还没有令牌桶时使用 [DEFAULT_RATE_LIMIT]
+pub fn host_bucket(host: &str, rate_limit: Option<&RateLimit>) -> TokenBucket {
+    let mut buckets = HOST_BUCKETS.lock().unwrap_or_else(|e| e.into_inner());
+    let limit = rate_limit.unwrap_or(&DEFAULT_RATE_LIMIT);
+    let capacity = limit.max_count as usize;
+    let rate = Duration::from_secs_f64(limit.fill_duration.max(0.0));
+
+    let (bucket, explicit) = buckets
+        .entry(host.to_lowercase())
+        .or_insert_with(|| (TokenBucket::new(capacity, rate), rate_limit.is_some()));
+    if rate_limit.is_some() {
+        // 默认的限制可以被书源设置的限制替换，书源设置的限制之间只能收紧
+        *bucket = if *explicit {
+            bucket.restrict(capacity, rate)
+        } else {
+            bucket.with_limit(capacity, rate)
+        };
+        *explicit = true;
+    }
+    bucket.clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_host_bucket() {
+        let rate_limit = RateLimit {
+            max_count: 1,
+            fill_duration: 60.0,
+        };
+        let bucket = host_bucket("shared.example.com", Some(&rate_limit));
+        assert!(bucket.try_acquire().is_ok());
+
+        // 同一个域名共享令牌，其他域名不受影响
+        let other = RateLimit {
+            max_count: 10,
+            fill_duration: 0.1,
+        };
+        assert!(host_bucket("Shared.Example.com", Some(&other))
+            .try_acquire()
+            .is_err());
+        assert!(host_bucket("other.example.com", Some(&rate_limit))
+            .try_acquire()
+            .is_ok());
+    }
+
+    #[test]
+    fn test_host_bucket_strictest() {
+        let loose = RateLimit {
+            max_count: 10,
+            fill_duration: 0.1,
+        };
+        let strict = RateLimit {
+            max_count: 2,
+            fill_duration: 60.0,
+        };
+
+        // 后注册的更严格的限制生效，之后更宽松的限制不会放宽
+        let bucket = host_bucket("strict.example.com", Some(&loose));
+        assert_eq!(bucket.capacity, 10);
+        let bucket = host_bucket("strict.example.com", Some(&strict));
+        assert_eq!((bucket.capacity, bucket.rate), (2, Duration::from_secs(60)));
+        let bucket = host_bucket("strict.example.com", Some(&loose));
+        assert_eq!((bucket.capacity, bucket.rate), (2, Duration::from_secs(60)));
+
+        // 没有设置限制的书源同样使用这个令牌桶
+        let bucket = host_bucket("strict.example.com", None);
+        assert_eq!((bucket.capacity, bucket.rate), (2, Duration::from_secs(60)));
+        assert!(bucket.try_acquire().is_ok());
+        assert!(bucket.try_acquire().is_ok());
+        assert!(host_bucket("strict.example.com", None)
+            .try_acquire()
+            .is_err());
+    }
+
+    #[test]
+    fn test_host_bucket_default() {
+        // 没有设置限制时使用默认的限制
+        let bucket = host_bucket("default.example.com", None);
+        assert_eq!(bucket.capacity, DEFAULT_RATE_LIMIT.max_count as usize);
+
+        // 书源设置的限制可以替换默认的限制
+        let rate_limit = RateLimit {
+            max_count: 100,
+            fill_duration: 0.01,
+        };
+        let bucket = host_bucket("default.example.com", Some(&rate_limit));
+        assert_eq!(
+            (bucket.capacity, bucket.rate),
+            (100, Duration::from_millis(10))
+        );
+    }
+}
@@ -1,52 +1,105 @@
-use std::sync::Arc;
-
-use tokio::{
-    sync::Semaphore,
-    time::{interval, Duration},
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
 };
-use tokio_util::sync::CancellationToken;
 
+/// 令牌桶，获取令牌时根据距离上次补充的时间计算新增的令牌，不需要后台任务
 #[derive(Debug, Clone)]
 pub struct TokenBucket {
-    pub sem: Arc<Semaphore>,
-    pub cancel_token: CancellationToken,
+    pub capacity: usize,
+    /// 每隔多久补充一个令牌
+    pub rate: Duration,
+    state: Arc<Mutex<BucketState>>,
+}
+
+#[derive(Debug)]
+struct BucketState {
+    tokens: f64,
+    last_refill: Instant,
 }
 
 impl TokenBucket {
     pub fn new(capacity: usize, rate: Duration) -> Self {
-        let sem = Arc::new(Semaphore::new(capacity));
-        let cancel_token = CancellationToken::new();
-        tokio::spawn({
-            let sem = sem.clone();
-            let cancel_token = cancel_token.clone();
-            let mut interval = interval(rate);
-            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
-
-            async move {
-                loop {
-                    tokio::select! {
-                        _ = cancel_token.cancelled() => break,
-                        _ = interval.tick() => {
-                            if sem.available_permits() < capacity {
-                                sem.add_permits(1);
-                            }
-                        }
-                    }
-                }
-            }
-        });
-
-        Self { sem, cancel_token }
+        let capacity = capacity.max(1);
+        Self {
+            capacity,
+            rate,
+            state: Arc::new(Mutex::new(BucketState {
+                tokens: capacity as f64,
+                last_refill: Instant::now(),
+            })),
+        }
+    }
+
+    /// 收紧限制，容量取较小值、补充间隔取较大值，和原来的桶共享剩余的令牌
+    pub fn restrict(&self, capacity: usize, rate: Duration) -> Self {
+        Self {
+            capacity: self.capacity.min(capacity.max(1)),
+            rate: self.rate.max(rate),
+            state: self.state.clone(),
+        }
+    }
+
+    /// 使用新的限制，和原来的桶共享剩余的令牌
+    pub fn with_limit(&self, capacity: usize, rate: Duration) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            rate,
+            state: self.state.clone(),
+        }
     }
 
+    /// 尝试获取一个令牌，令牌不足时返回还需要等待的时间
+    pub fn try_acquire(&self) -> Result<(), Duration> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+
+        if self.rate.is_zero() {
+            state.tokens = self.capacity as f64;
+        } else {
+            let elapsed = now.duration_since(state.last_refill).as_secs_f64();
+            state.tokens =
+                (state.tokens + elapsed / self.rate.as_secs_f64()).min(self.capacity as f64);
+        }
+        state.last_refill = now;
+
+        if state.tokens >= 1.0 {
+            state.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(self.rate.mul_f64(1.0 - state.tokens))
+        }
+    }
+
+    /// 获取一个令牌，令牌不足时等待补充
     pub async fn acquire(&self) {
-        let permits = self.sem.acquire().await.unwrap();
-        permits.forget();
+        while let Err(wait) = self.try_acquire() {
+            tokio::time::sleep(wait).await;
+        }
     }
 }
 
-impl Drop for TokenBucket {
-    fn drop(&mut self) {
-        self.cancel_token.cancel();
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_bucket_without_runtime() {
+        let bucket = TokenBucket::new(2, Duration::from_secs(60));
+        assert!(bucket.try_acquire().is_ok());
+        assert!(bucket.clone().try_acquire().is_ok());
+        // 克隆共享同一个桶
+        let wait = bucket.try_acquire().unwrap_err();
+        assert!(wait > Duration::from_secs(50) && wait <= Duration::from_secs(60));
+    }
+
+    #[tokio::test]
+    async fn test_token_bucket_refill() {
+        let bucket = TokenBucket::new(1, Duration::from_millis(50));
+        let start = Instant::now();
+        bucket.acquire().await;
+        bucket.acquire().await;
+        bucket.acquire().await;
+        assert!(start.elapsed() >= Duration::from_millis(100));
     }
 }
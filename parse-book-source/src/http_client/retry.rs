@@ -0,0 +1,114 @@
+//! 请求重试，按 [RetryPolicy] 计算等待时间，支持 `Retry-After` 响应头
+
+use super::{HttpRequest, HttpResponse};
+use crate::{ParseError, RetryPolicy};
+use rand::Rng;
+use std::time::{Duration, SystemTime};
+
+impl RetryPolicy {
+    /// 请求是否可以重试，默认只重试 GET 和 HEAD
+    pub fn can_retry(&self, request: &HttpRequest) -> bool {
+        self.non_idempotent
+            || request.method.eq_ignore_ascii_case("GET")
+            || request.method.eq_ignore_ascii_case("HEAD")
+    }
+
+    /// 响应是否需要重试
+    pub fn should_retry(&self, res: &HttpResponse) -> bool {
+        self.status_codes.contains(&res.status)
+    }
+
+    /// 请求错误是否需要重试，只重试连接失败和超时，
+    /// 链接无效、证书错误和解码失败等重试也不会成功
+    pub fn should_retry_error(&self, err: &ParseError) -> bool {
+        match err {
+            ParseError::Reqwest(e) => e.is_connect() || e.is_timeout(),
+            ParseError::IO(e) => matches!(
+                e.kind(),
+                std::io::ErrorKind::ConnectionRefused
+                    | std::io::ErrorKind::ConnectionReset
+                    | std::io::ErrorKind::ConnectionAborted
+                    | std::io::ErrorKind::TimedOut
+            ),
+            _ => false,
+        }
+    }
+
+    /// 第 `attempt` 次请求失败后的等待时间，`attempt` 从 1 开始
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let delay = self
+            .base_delay
+            .saturating_mul(1u64 << attempt.saturating_sub(1).min(32))
+            .min(self.max_delay);
+
+        // 等待时间在 [delay / 2, delay] 之间随机
+        let delay = if self.jitter && delay > 1 {
+            rand::thread_rng().gen_range(delay / 2..=delay)
+        } else {
+            delay
+        };
+        Duration::from_millis(delay)
+    }
+
+    /// 失败响应的等待时间，优先使用 `Retry-After` 响应头，最长不超过 `max_delay`
+    pub fn delay_for(&self, attempt: u32, res: Option<&HttpResponse>) -> Duration {
+        res.and_then(|res| res.header("retry-after"))
+            .and_then(parse_retry_after)
+            .map(|delay| delay.min(Duration::from_millis(self.max_delay)))
+            .unwrap_or_else(|| self.backoff(attempt))
+    }
+}
+
+/// 解析 `Retry-After`，支持秒数和 HTTP 日期（`Sun, 06 Nov 1994 08:49:37 GMT`）
+pub fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(
+        date.duration_since(SystemTime::now())
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff() {
+        let policy = RetryPolicy {
+            base_delay: 100,
+            max_delay: 1000,
+            jitter: false,
+            ..Default::default()
+        };
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(3), Duration::from_millis(400));
+        assert_eq!(policy.backoff(10), Duration::from_millis(1000));
+
+        let policy = RetryPolicy {
+            jitter: true,
+            ..policy
+        };
+        for _ in 0..20 {
+            let delay = policy.backoff(2);
+            assert!(delay >= Duration::from_millis(100) && delay <= Duration::from_millis(200));
+        }
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        assert_eq!(parse_retry_after("120"), Some(Duration::from_secs(120)));
+        let date = httpdate::fmt_http_date(SystemTime::now() + Duration::from_secs(3600));
+        let delay = parse_retry_after(&date).unwrap();
+        assert!(delay > Duration::from_secs(3590) && delay <= Duration::from_secs(3600));
+        assert_eq!(
+            parse_retry_after("Sun, 06 Nov 1994 08:49:37 GMT"),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(parse_retry_after("soon"), None);
+    }
+}
@@ -0,0 +1,233 @@
+//! 发送请求的传输层，默认使用 [reqwest]，测试和复现问题时可以换成 [crate::Replayer]
+
+use super::cookie::{self, CookieStoreMutex};
+use crate::{HttpConfig, Result};
+use anyhow::anyhow;
+use async_trait::async_trait;
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue},
+    Certificate, Client, ClientBuilder, Method, Proxy, Url,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::Debug,
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+    time::Duration,
+};
+
+/// 传输层的请求，链接已经是绝对链接
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct HttpRequest {
+    pub method: String,
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub headers: Vec<(String, String)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+}
+
+impl HttpRequest {
+    pub fn get(url: &str) -> Self {
+        Self {
+            method: "GET".into(),
+            url: url.into(),
+            ..Default::default()
+        }
+    }
+}
+
+/// 传输层的响应
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HttpResponse {
+    /// 重定向之后的实际链接
+    pub url: String,
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl HttpResponse {
+    /// 获取响应头，名称不区分大小写
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+#[async_trait]
+pub trait Transport: Debug + Send + Sync {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse>;
+}
+
+/// 使用 [reqwest] 发送请求
+#[derive(Debug, Clone)]
+pub struct ReqwestTransport {
+    pub client: Client,
+}
+
+impl ReqwestTransport {
+    pub fn new(config: &HttpConfig) -> Result<Self> {
+        Self::with_cookies(config, cookie::new_store())
+    }
+
+    /// 使用指定的 cookie 创建，多个传输层可以共享同一份 cookie
+    pub fn with_cookies(config: &HttpConfig, cookies: Arc<CookieStoreMutex>) -> Result<Self> {
+        let mut client = ClientBuilder::new().cookie_provider(cookies);
+
+        if let Some(header) = &config.header {
+            let mut headers = HeaderMap::new();
+
+            for (k, v) in header {
+                headers.insert(
+                    HeaderName::try_from(k)
+                        .map_err(|e| anyhow!("header name is not valid: {}", e))?,
+                    HeaderValue::from_str(v)
+                        .map_err(|e| anyhow!("header value is not valid: {}", e))?,
+                );
+            }
+            client = client.default_headers(headers);
+        }
+
+        if let Some(timeout) = config.timeout {
+            client = client.timeout(Duration::from_millis(timeout));
+        }
+
+        if let Some(proxy) = &config.proxy {
+            client =
+                client.proxy(Proxy::all(proxy).map_err(|e| anyhow!("proxy is not valid: {}", e))?);
+        }
+
+        for (host, addr) in config.resolve.iter().flatten() {
+            // 端口由链接决定，这里的端口会被忽略
+            let addr = addr
+                .parse::<SocketAddr>()
+                .or_else(|_| addr.parse::<IpAddr>().map(|ip| SocketAddr::new(ip, 0)))
+                .map_err(|e| anyhow!("resolve address of {} is not valid: {}", host, e))?;
+            client = client.resolve(host, addr);
+        }
+
+        if config.accept_invalid_certs == Some(true) {
+            client = client.danger_accept_invalid_certs(true);
+        }
+
+        if let Some(ca_cert) = &config.ca_cert {
+            let pem = if ca_cert.trim_start().starts_with("-----BEGIN") {
+                ca_cert.as_bytes().to_vec()
+            } else {
+                std::fs::read(ca_cert).map_err(|e| anyhow!("read {} failed: {}", ca_cert, e))?
+            };
+            let cert =
+                Certificate::from_pem(&pem).map_err(|e| anyhow!("caCert is not valid: {}", e))?;
+            client = client.add_root_certificate(cert);
+        }
+
+        Ok(Self {
+            client: client.build()?,
+        })
+    }
+}
+
+#[async_trait]
+impl Transport for ReqwestTransport {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse> {
+        let method = Method::from_bytes(request.method.as_bytes())
+            .map_err(|e| anyhow!("request method is not valid: {}", e))?;
+
+        let mut builder = self.client.request(method, &request.url);
+        for (k, v) in &request.headers {
+            builder = builder.header(
+                HeaderName::try_from(k).map_err(|e| anyhow!("header name is not valid: {}", e))?,
+                HeaderValue::from_str(v)
+                    .map_err(|e| anyhow!("header value is not valid: {}", e))?,
+            );
+        }
+        if let Some(body) = request.body {
+            builder = builder.body(body);
+        }
+
+        let res = builder.send().await?;
+        let url = res.url().to_string();
+        let status = res.status().as_u16();
+        let headers = res
+            .headers()
+            .iter()
+            .filter_map(|(k, v)| Some((k.to_string(), v.to_str().ok()?.to_string())))
+            .collect();
+
+        Ok(HttpResponse {
+            url,
+            status,
+            headers,
+            body: res.bytes().await?.to_vec(),
+        })
+    }
+}
+
+/// 读取 `file://` 链接指向的本地文件
+pub async fn read_file(url: &str) -> Result<HttpResponse> {
+    let path = Url::parse(url)
+        .ok()
+        .and_then(|url| url.to_file_path().ok())
+        .ok_or_else(|| anyhow!("file url is not valid: {}", url))?;
+
+    let body = tokio::fs::read(&path)
+        .await
+        .map_err(|e| anyhow!("read {} failed: {}", path.display(), e))?;
+
+    Ok(HttpResponse {
+        url: url.to_string(),
+        status: 200,
+        headers: vec![],
+        body,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_server::{serve, TestResponse};
+    use std::collections::HashMap;
+
+    #[tokio::test]
+    async fn test_reqwest_transport_options() {
+        let base_url = serve(|req| TestResponse::ok(req.path.clone()));
+        let port = base_url.rsplit(':').next().unwrap();
+
+        // 固定域名解析
+        let config = HttpConfig {
+            resolve: Some(HashMap::from([("book.test".into(), "127.0.0.1".into())])),
+            ..Default::default()
+        };
+        let transport = ReqwestTransport::new(&config).unwrap();
+        let url = format!("http://book.test:{}/book/1", port);
+        let res = transport.send(HttpRequest::get(&url)).await.unwrap();
+        assert_eq!(res.body, b"/book/1");
+
+        // http 代理收到的是完整链接
+        let config = HttpConfig {
+            proxy: Some(base_url.clone()),
+            ..Default::default()
+        };
+        let transport = ReqwestTransport::new(&config).unwrap();
+        let res = transport
+            .send(HttpRequest::get("http://book.invalid/book/1"))
+            .await
+            .unwrap();
+        assert_eq!(res.body, b"http://book.invalid/book/1");
+
+        let config = HttpConfig {
+            ca_cert: Some("-----BEGIN CERTIFICATE-----\ninvalid\n-----END CERTIFICATE-----".into()),
+            ..Default::default()
+        };
+        assert!(ReqwestTransport::new(&config).is_err());
+
+        let config = HttpConfig {
+            resolve: Some(HashMap::from([("book.test".into(), "localhost".into())])),
+            ..Default::default()
+        };
+        assert!(ReqwestTransport::new(&config).is_err());
+    }
+}
@@ -1,31 +1,77 @@
+use ::regex::Regex;
+use analyzer::{codec::url_encode, js::split_js};
 use anyhow::anyhow;
-use serde_json::json;
+use serde_json::{json, Value};
+use std::{
+    collections::{HashSet, VecDeque},
+    sync::LazyLock,
+};
+use utils::{replace_all, resolve_url, same_chapter};
 
 pub mod analyzer;
 pub mod book;
 pub mod book_source;
+pub mod check;
 pub mod error;
 pub mod http_client;
 pub mod utils;
 pub use analyzer::*;
 pub use book::*;
 pub use book_source::*;
+pub use check::*;
 pub use error::*;
 pub use http_client::*;
 
+/// 模板中直接引用的变量，例如 `{{key}}`
+static BARE_VARIABLE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\{\{\s*(\w+)\s*\}\}").unwrap());
+
+/// 把模板中直接引用的字符串变量替换成 `encode` 编码之后的值，
+/// 其他表达式（例如 `{{key@encode:base64}}`、脚本）之后使用原始值渲染
+fn substitute_variables(
+    template: &str,
+    extra: &Value,
+    encode: impl Fn(&str) -> Result<String>,
+) -> Result<String> {
+    replace_all(&BARE_VARIABLE, template, |captures| {
+        match extra.get(&captures[1]) {
+            Some(Value::String(value)) => encode(value),
+            _ => Ok(captures[0].to_string()),
+        }
+    })
+}
+
+/// 目录最多请求的页数，避免翻页规则有误时一直请求
+pub const MAX_TOC_PAGES: usize = 500;
+
+/// 正文默认最多请求的页数，见 [RuleContent::Follow]
+pub const MAX_CONTENT_PAGES: usize = 50;
+
 #[derive(Debug, Clone)]
 pub struct BookSourceParser {
     pub book_source: BookSource,
     pub http_client: HttpClient,
     pub analyzer: AnalyzerManager,
-    pub temp: Option<String>,
+    /// 最近一次获取的详情页，目录在详情页中时使用
+    pub temp: Option<Page>,
 }
 
 impl TryFrom<BookSource> for BookSourceParser {
     type Error = ParseError;
 
     fn try_from(book_source: BookSource) -> Result<Self> {
-        let mut http_config = book_source.http_config.clone();
+        Self::with_http_defaults(book_source, &HttpConfig::default())
+    }
+}
+
+impl BookSourceParser {
+    pub fn new(book_source: BookSource) -> Result<Self> {
+        Self::try_from(book_source)
+    }
+
+    /// 创建解析器，书源没有设置的代理、域名解析、证书等网络选项使用 `defaults`
+    pub fn with_http_defaults(book_source: BookSource, defaults: &HttpConfig) -> Result<Self> {
+        let mut http_config = book_source.http_config.clone().with_defaults(defaults);
         if let Some(ref header) = book_source.header {
             http_config.header = Some(serde_json::from_str(header)?);
         }
@@ -34,80 +80,162 @@ impl TryFrom<BookSource> for BookSourceParser {
             http_config.timeout = Some(*response_time);
         }
 
+        let mut analyzer = AnalyzerManager::new()?;
+        analyzer.base_url = book_source.book_source_url.clone();
+        // 加载时编译所有规则，规则无效时直接报错
+        book_source.compile(&analyzer)?;
+
         Ok(Self {
             http_client: HttpClient::new(&book_source.book_source_url, &http_config)?,
             book_source,
-            analyzer: AnalyzerManager::new()?,
+            analyzer,
             temp: None,
         })
     }
-}
-
-impl BookSourceParser {
-    pub fn new(book_source: BookSource) -> Result<Self> {
-        Self::try_from(book_source)
-    }
 
     /// 获取分类信息
     pub async fn get_explores(&mut self) -> Result<ExploreList> {
+        let name = self.book_source.book_source_name.clone();
+        self.http_client.stage = Some(Stage::Explore);
         if let Some(ref explore_url) = self.book_source.explore_url {
             if let Some(ref rule_explore_item) = self.book_source.rule_explore_item {
                 let res = self
                     .http_client
-                    .get(&self.book_source.book_source_url)
-                    .await?
-                    .text()
-                    .await?;
-
-                let list = self.analyzer.get_element(explore_url, &res)?;
-
-                let res = list
-                    .into_iter()
-                    .flat_map(|item| {
-                        rule_explore_item.parse_to_explore_item(&mut self.analyzer, &item)
-                    })
-                    .collect::<Vec<_>>();
-                return Ok(res);
+                    .get_text(&self.book_source.book_source_url)
+                    .await
+                    .field("bookSourceUrl")
+                    .stage(&name, Stage::Explore, None)?;
+
+                let list = self
+                    .analyzer
+                    .get_element_async(explore_url, &res, &self.http_client)
+                    .await
+                    .field("exploreUrl")
+                    .stage(&name, Stage::Explore, None)?;
+
+                let mut items = vec![];
+                for item in list {
+                    items.push(
+                        rule_explore_item
+                            .parse_to_explore_item(&mut self.analyzer, &self.http_client, &item)
+                            .await
+                            .stage(&name, Stage::Explore, Some("ruleExploreItem")),
+                    );
+                }
+                return collect_items(items);
             } else {
-                return Ok(serde_json::from_str(explore_url)?);
+                return serde_json::from_str(explore_url)
+                    .map_err(ParseError::from)
+                    .field("exploreUrl")
+                    .stage(&name, Stage::Explore, None);
             }
         }
 
         Ok(vec![])
     }
 
+    /// 渲染链接或请求体模板，没有表达式和脚本时原样返回
+    async fn render(&mut self, template: &str, extra: &Value) -> Result<String> {
+        if !template.contains("{{") && split_js(template).is_none() {
+            return Ok(template.to_string());
+        }
+        self.analyzer
+            .get_string_async(template, "", Some(extra.clone()), &self.http_client)
+            .await
+    }
+
+    /// 发送请求，`extra` 不为空时使用它渲染链接和请求体中的表达式
+    ///
+    /// 变量的值是原始值，链接和表单请求体中直接引用的 `{{key}}` 按请求选项的字符集
+    /// 或者 `httpConfig.searchCharset` 进行 url 编码，JSON 请求体中转义成 JSON 字符串的内容
+    async fn request(
+        &mut self,
+        url: &str,
+        options: &RequestOptions,
+        extra: Option<Value>,
+    ) -> Result<Page> {
+        let (url, body) = match extra {
+            Some(extra) => {
+                let charset = options
+                    .charset
+                    .clone()
+                    .or(self.book_source.http_config.search_charset.clone())
+                    .unwrap_or("utf-8".into());
+                let url_encoded = |value: &str| url_encode(value, &charset);
+
+                let url = substitute_variables(url, &extra, url_encoded)?;
+                let url = self.render(&url, &extra).await?;
+                let body = match options.body_string() {
+                    Some(body) => {
+                        let body = if options.is_json_body() {
+                            substitute_variables(&body, &extra, |value| {
+                                let quoted = serde_json::to_string(value)?;
+                                Ok(quoted[1..quoted.len() - 1].to_string())
+                            })?
+                        } else {
+                            substitute_variables(&body, &extra, url_encoded)?
+                        };
+                        Some(self.render(&body, &extra).await?)
+                    }
+                    None => None,
+                };
+                (url, body)
+            }
+            None => (url.to_string(), options.body_string()),
+        };
+
+        self.http_client.request_page(&url, options, body).await
+    }
+
     /// 搜索书籍
     pub async fn search_books(&mut self, key: &str, page: u32, page_size: u32) -> Result<BookList> {
-        let url = self.analyzer.get_string(
-            &self.book_source.search_url,
-            "",
-            Some(json!({
-                "key": key,
-                "page": page,
-                "page_size": page_size,
-            })),
-        )?;
-
-        let mut res = String::new();
-
-        for i in url.split(",") {
-            res = self.http_client.get(i).await?.text().await?;
-        }
+        let name = self.book_source.book_source_name.clone();
+        self.http_client.stage = Some(Stage::Search);
+        let (url, options) = RequestOptions::from_url(&self.book_source.search_url)
+            .field("searchUrl")
+            .stage(&name, Stage::Search, None)?;
+
+        // 链接和表单中的 {{key}} 会进行 url 编码，见 [BookSourceParser::request]
+        let res = self
+            .request(
+                &url,
+                &options,
+                Some(json!({
+                    "key": key,
+                    "rawKey": key,
+                    "page": page,
+                    "page_size": page_size,
+                })),
+            )
+            .await
+            .field("searchUrl")
+            .stage(&name, Stage::Search, None)?;
 
         let list = self
             .analyzer
-            .get_element(&self.book_source.rule_search.book_list, &res)?;
+            .get_element_async(
+                &self.book_source.rule_search.book_list,
+                &res.text,
+                &self.http_client,
+            )
+            .await
+            .field("bookList")
+            .stage(&name, Stage::Search, Some("ruleSearch"))?;
 
-        let res = list
-            .into_iter()
-            .flat_map(|item| {
+        let mut items = vec![];
+        for item in list {
+            items.push(
                 self.book_source
                     .rule_search
-                    .parse_to_book_list_item(&mut self.analyzer, &item)
-            })
-            .collect::<Vec<_>>();
+                    .parse_to_book_list_item(&mut self.analyzer, &self.http_client, &item)
+                    .await
+                    .stage(&name, Stage::Search, Some("ruleSearch")),
+            );
+        }
 
-        Ok(res)
+        let mut list = collect_items(items)?;
+        list.iter_mut().for_each(|item| item.resolve_urls(&res.url));
+        Ok(list)
     }
 
     /// 使用explore_item的url获取书籍列表
@@ -117,47 +245,78 @@ impl BookSourceParser {
         page: u32,
         page_size: u32,
     ) -> Result<BookList> {
-        if self.book_source.rule_explore.is_none() {
-            return Err(anyhow!("explore rule is none").into());
+        let name = self.book_source.book_source_name.clone();
+        self.http_client.stage = Some(Stage::Explore);
+        let Some(rule_explore) = self.book_source.rule_explore.as_ref() else {
+            return Err(
+                ParseError::from(anyhow!("ruleExplore is not set")).with_stage(
+                    &name,
+                    Stage::Explore,
+                    None,
+                ),
+            );
+        };
+        let rule_explore = rule_explore.clone();
+        let (url, options) =
+            RequestOptions::from_url(url)
+                .field("exploreUrl")
+                .stage(&name, Stage::Explore, None)?;
+        let res = self
+            .request(
+                &url,
+                &options,
+                Some(json!({
+                    "page": page,
+                    "page_size": page_size,
+                })),
+            )
+            .await
+            .field("exploreUrl")
+            .stage(&name, Stage::Explore, None)?;
+
+        let list = self
+            .analyzer
+            .get_element_async(&rule_explore.book_list, &res.text, &self.http_client)
+            .await
+            .field("bookList")
+            .stage(&name, Stage::Explore, Some("ruleExplore"))?;
+
+        let mut items = vec![];
+        for item in list {
+            items.push(
+                rule_explore
+                    .parse_to_book_list_item(&mut self.analyzer, &self.http_client, &item)
+                    .await
+                    .stage(&name, Stage::Explore, Some("ruleExplore")),
+            );
         }
-        let url = self.analyzer.get_string(
-            url,
-            "",
-            Some(json!({
-                "page": page,
-                "page_size": page_size,
-            })),
-        )?;
-
-        let res = self.http_client.get(url.as_str()).await?.text().await?;
-
-        let list = self.analyzer.get_element(
-            &self.book_source.rule_explore.as_ref().unwrap().book_list,
-            &res,
-        )?;
-
-        let res = list
-            .into_iter()
-            .flat_map(|item| {
-                self.book_source
-                    .rule_explore
-                    .as_ref()
-                    .unwrap()
-                    .parse_to_book_list_item(&mut self.analyzer, &item)
-            })
-            .collect::<Vec<_>>();
 
-        Ok(res)
+        let mut list = collect_items(items)?;
+        list.iter_mut().for_each(|item| item.resolve_urls(&res.url));
+        Ok(list)
     }
 
     /// 获取书籍信息
     pub async fn get_book_info(&mut self, book_url: &str) -> Result<BookInfo> {
-        let res = self.http_client.get(book_url).await?.text().await?;
+        let name = self.book_source.book_source_name.clone();
+        self.http_client.stage = Some(Stage::Info);
+        let res = self
+            .http_client
+            .get_page(book_url)
+            .await
+            .field("ruleSearch.bookUrl")
+            .stage(&name, Stage::Info, None)?;
 
         let book_info = self
             .book_source
             .rule_book_info
-            .parse_to_book_info(&mut self.analyzer, &res);
+            .parse_to_book_info(&mut self.analyzer, &self.http_client, &res.text)
+            .await
+            .stage(&name, Stage::Info, Some("ruleBookInfo"))
+            .map(|mut book_info| {
+                book_info.resolve_urls(&res.url);
+                book_info
+            });
 
         self.temp = Some(res);
 
@@ -165,74 +324,860 @@ impl BookSourceParser {
     }
 
     pub async fn get_chapters(&mut self, toc_url: &str) -> Result<Vec<Chapter>> {
-        // 如果toc_url是http开头的url，直接请求
-        let res = if toc_url.starts_with("/") || toc_url.starts_with("http") {
-            self.http_client.get(toc_url).await?.text().await?
+        let name = self.book_source.book_source_name.clone();
+        self.http_client.stage = Some(Stage::Toc);
+        let toc_url = toc_url.trim();
+        let mut res = if toc_url.is_empty() {
+            // 目录在详情页中时使用 get_book_info 请求到的页面
+            self.temp
+                .clone()
+                .ok_or(anyhow!(
+                    "tocUrl is empty and no book info page has been loaded"
+                ))
+                .map_err(ParseError::from)
+                .field("ruleBookInfo.tocUrl")
+                .stage(&name, Stage::Toc, None)?
         } else {
-            self.temp.clone().ok_or(anyhow!("temp is none"))?
+            // 相对链接相对于详情页解析，没有请求过详情页时相对于书源地址
+            let base = self
+                .temp
+                .as_ref()
+                .map(|page| page.url.clone())
+                .unwrap_or_else(|| self.book_source.book_source_url.clone());
+            let (url, options) = RequestOptions::from_url(&resolve_url(&base, toc_url))
+                .field("ruleBookInfo.tocUrl")
+                .stage(&name, Stage::Toc, None)?;
+            self.request(&url, &options, None)
+                .await
+                .field("ruleBookInfo.tocUrl")
+                .stage(&name, Stage::Toc, None)?
         };
 
+        let rule_toc = self.book_source.rule_toc.clone();
+        let mut chapters =
+            self.parse_toc_page(&res)
+                .await
+                .stage(&name, Stage::Toc, Some("ruleToc"))?;
+
+        let max_pages = if rule_toc.page_count.is_empty() {
+            MAX_TOC_PAGES
+        } else {
+            self.analyzer
+                .get_string_async(&rule_toc.page_count, &res.text, None, &self.http_client)
+                .await
+                .and_then(|count| Ok(count.trim().parse::<usize>()?))
+                .field("pageCount")
+                .stage(&name, Stage::Toc, Some("ruleToc"))?
+                .min(MAX_TOC_PAGES)
+        };
+
+        // 记录请求过和等待请求的链接，避免规则返回上一页或者当前页时死循环
+        let mut visited = HashSet::from([toc_url.to_string(), res.url.clone()]);
+        // 记录解析到的章节，链接模板没有页数限制时，某一页没有新章节就停止
+        let mut seen = chapters
+            .iter()
+            .map(|chapter| (chapter.chapter_name.clone(), chapter.chapter_url.clone()))
+            .collect::<HashSet<_>>();
+        let mut pending = VecDeque::new();
+        let mut page = 1;
+        while !rule_toc.next_toc_url.is_empty() {
+            let next = self
+                .analyzer
+                .get_string_async(
+                    &rule_toc.next_toc_url,
+                    &res.text,
+                    Some(json!({ "page": page + 1 })),
+                    &self.http_client,
+                )
+                .await
+                .field("nextTocUrl")
+                .stage(&name, Stage::Toc, Some("ruleToc"))?;
+
+            for next_url in next.lines().flat_map(|line| line.split("  ")) {
+                let next_url = next_url.trim();
+                if next_url.is_empty() {
+                    continue;
+                }
+                let (url, options) = RequestOptions::from_url(next_url)
+                    .field("nextTocUrl")
+                    .stage(&name, Stage::Toc, Some("ruleToc"))?;
+                // 下一页的链接相对于当前页解析
+                let url = resolve_url(&res.url, &url);
+                if visited.insert(url.clone()) {
+                    pending.push_back((url, options));
+                }
+            }
+
+            if page >= max_pages {
+                break;
+            }
+            let Some((url, options)) = pending.pop_front() else {
+                break;
+            };
+            res = self
+                .request(&url, &options, None)
+                .await
+                .field("nextTocUrl")
+                .stage(&name, Stage::Toc, Some("ruleToc"))?;
+            visited.insert(res.url.clone());
+            page += 1;
+            // 请求失败的页面不是目录，例如超过最后一页时返回的 404
+            if !(200..300).contains(&res.status) {
+                break;
+            }
+
+            let page_chapters =
+                self.parse_toc_page(&res)
+                    .await
+                    .stage(&name, Stage::Toc, Some("ruleToc"))?;
+            let mut has_new = false;
+            for chapter in &page_chapters {
+                has_new |= seen.insert((chapter.chapter_name.clone(), chapter.chapter_url.clone()));
+            }
+            if !has_new {
+                break;
+            }
+            chapters.extend(page_chapters);
+        }
+
+        Ok(rule_toc.arrange(chapters))
+    }
+
+    /// 解析一页目录中的章节，章节链接相对于这一页解析
+    async fn parse_toc_page(&mut self, res: &Page) -> Result<Vec<Chapter>> {
         let list = self
             .analyzer
-            .get_element(&self.book_source.rule_toc.chapter_list, &res)?;
+            .get_element_async(
+                &self.book_source.rule_toc.chapter_list,
+                &res.text,
+                &self.http_client,
+            )
+            .await
+            .field("chapterList")?;
 
-        let res = list
-            .into_iter()
-            .flat_map(|item| {
+        let mut items = vec![];
+        for item in list {
+            items.push(
                 self.book_source
                     .rule_toc
-                    .parse_to_chapter(&mut self.analyzer, &item)
-            })
-            .collect::<Vec<_>>();
+                    .parse_to_chapter(&mut self.analyzer, &self.http_client, &item)
+                    .await,
+            );
+        }
+
+        let mut list = collect_items(items)?;
+        list.iter_mut().for_each(|item| item.resolve_urls(&res.url));
+        Ok(list)
+    }
+
+    /// 书源是否需要登录
+    pub fn need_login(&self) -> bool {
+        self.book_source
+            .login_url
+            .as_deref()
+            .is_some_and(|url| !url.trim().is_empty())
+    }
 
-        Ok(res)
+    /// 使用账号和密码登录，登录后的 cookie 由 [HttpClient] 保存
+    pub async fn login(&mut self, username: &str, password: &str) -> Result<()> {
+        let name = self.book_source.book_source_name.clone();
+        self.http_client.stage = Some(Stage::Login);
+        self._login(username, password)
+            .await
+            .stage(&name, Stage::Login, None)
+    }
+
+    async fn _login(&mut self, username: &str, password: &str) -> Result<()> {
+        let login_url = self
+            .book_source
+            .login_url
+            .clone()
+            .filter(|url| !url.trim().is_empty())
+            .ok_or(ParseError::from(anyhow!("书源不需要登录")))
+            .field("loginUrl")?;
+        let (url, options) = RequestOptions::from_url(&login_url).field("loginUrl")?;
+
+        let res = self
+            .request(
+                &url,
+                &options,
+                Some(json!({
+                    "username": username,
+                    "password": password,
+                    "rawUsername": username,
+                    "rawPassword": password,
+                })),
+            )
+            .await
+            .field("loginUrl")?;
+
+        if let Some(login_check) = self.book_source.login_check.clone() {
+            let result = self
+                .analyzer
+                .get_string_async(&login_check, &res.text, None, &self.http_client)
+                .await
+                .field("loginCheck")?;
+            if result.trim().is_empty() {
+                return Err(ParseError::from(anyhow!("登录失败，请检查账号和密码"))
+                    .with_step(&login_check, &res.text)
+                    .with_field("loginCheck"));
+            }
+        }
+
+        // 登录之前缓存的页面可能缺少需要登录才能看到的内容
+        self.http_client.clear_cache()?;
+        self.http_client.save_cookies()
+    }
+
+    /// 使用文件对应的共享 cookie，之后的请求和登录都会保存到这个文件
+    pub fn with_cookie_file(mut self, path: impl Into<std::path::PathBuf>) -> Result<Self> {
+        self.http_client = self.http_client.with_cookie_file(path)?;
+        Ok(self)
+    }
+
+    /// 把响应缓存到指定目录，各个阶段的有效期由 `httpConfig.cache` 设置，
+    /// 登录和退出登录时会清空这个目录，不同书源应该使用不同的目录
+    pub fn with_cache_dir(mut self, dir: impl Into<std::path::PathBuf>) -> Self {
+        self.http_client = self.http_client.with_cache_dir(dir);
+        self
+    }
+
+    /// 退出登录，清空书源的 cookie 和缓存
+    pub fn logout(&self) -> Result<()> {
+        self.http_client.clear_cookies()?;
+        self.http_client.clear_cache()
     }
 
     pub async fn get_content(&mut self, chapter_url: &str) -> Result<String> {
-        let mut res = self.http_client.get(chapter_url).await?.text().await?;
+        let name = self.book_source.book_source_name.clone();
+        self.http_client.stage = Some(Stage::Content);
+        self._get_content(chapter_url)
+            .await
+            .and_then(|content| self.book_source.rule_content.cleanup().apply(&content))
+            .stage(&name, Stage::Content, Some("ruleContent"))
+    }
+
+    async fn _get_content(&mut self, chapter_url: &str) -> Result<String> {
+        let mut res = self
+            .http_client
+            .get_page(chapter_url)
+            .await
+            .field("ruleToc.chapterUrl")?;
 
         match &self.book_source.rule_content {
-            RuleContent::One { content } => self.analyzer.get_string(content, &res, None),
+            RuleContent::One { content, .. } => self
+                .analyzer
+                .get_string_async(content, &res.text, None, &self.http_client)
+                .await
+                .field("content"),
 
             RuleContent::More {
                 content,
                 next_content_url,
                 start,
                 end,
+                ..
             } => {
                 let end = self
                     .analyzer
-                    .get_string(end, &res, None)?
-                    .parse::<usize>()?;
+                    .get_string_async(end, &res.text, None, &self.http_client)
+                    .await
+                    .and_then(|end| Ok(end.parse::<usize>()?))
+                    .field("end")?;
                 let mut contents = vec![];
                 let mut start = *start;
 
                 loop {
-                    let content = self.analyzer.get_string(content, &res, None)?;
+                    let content = self
+                        .analyzer
+                        .get_string_async(content, &res.text, None, &self.http_client)
+                        .await
+                        .field("content")?;
                     contents.push(content);
 
                     if start > end {
                         break;
                     }
 
-                    let next_url = self.analyzer.get_string(
-                        next_content_url,
-                        &res,
-                        Some(json!({
-                            "index": start,
-                        })),
-                    )?;
+                    let next_url = self
+                        .analyzer
+                        .get_string_async(
+                            next_content_url,
+                            &res.text,
+                            Some(json!({
+                                "index": start,
+                            })),
+                            &self.http_client,
+                        )
+                        .await
+                        .field("nextContentUrl")?;
+                    // 下一页的链接相对于当前页解析
                     res = self
                         .http_client
-                        .get(next_url.as_str())
-                        .await?
-                        .text()
-                        .await?;
+                        .get_page(&resolve_url(&res.url, &next_url))
+                        .await
+                        .field("nextContentUrl")?;
                     start += 1;
                 }
 
                 Ok(contents.join("  "))
             }
+
+            RuleContent::Follow {
+                content,
+                next_content_url,
+                max_pages,
+                ..
+            } => {
+                let max_pages = max_pages.unwrap_or(MAX_CONTENT_PAGES).max(1);
+                // 以重定向之后的第一页判断下一页是否还属于本章
+                let chapter = res.url.clone();
+                let mut visited = HashSet::from([chapter.clone()]);
+                let mut contents = vec![];
+
+                loop {
+                    let content = self
+                        .analyzer
+                        .get_string_async(content, &res.text, None, &self.http_client)
+                        .await
+                        .field("content")?;
+                    contents.push(content);
+
+                    if contents.len() >= max_pages {
+                        break;
+                    }
+
+                    let next_url = self
+                        .analyzer
+                        .get_string_async(next_content_url, &res.text, None, &self.http_client)
+                        .await
+                        .field("nextContentUrl")?;
+                    let next_url = next_url.trim();
+                    if next_url.is_empty() {
+                        break;
+                    }
+                    let next_url = resolve_url(&res.url, next_url);
+                    if !same_chapter(&chapter, &next_url) || !visited.insert(next_url.clone()) {
+                        break;
+                    }
+
+                    res = self
+                        .http_client
+                        .get_page(&next_url)
+                        .await
+                        .field("nextContentUrl")?;
+                    visited.insert(res.url.clone());
+                }
+
+                // 每一页另起一段
+                Ok(contents.join("\n"))
+            }
+        }
+    }
+}
+
+/// 逐项解析列表，只有全部失败时才返回第一个错误，以便定位规则问题
+fn collect_items<T>(items: Vec<Result<T>>) -> Result<Vec<T>> {
+    let mut res = vec![];
+    let mut first_error = None;
+    for item in items {
+        match item {
+            Ok(item) => res.push(item),
+            Err(e) => {
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+
+    match first_error {
+        Some(e) if res.is_empty() => Err(e),
+        _ => Ok(res),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_server::{serve, TestResponse};
+    use std::collections::BTreeMap;
+
+    /// 测试用的书源，`overrides` 中的字段覆盖默认值，没有覆盖的规则都为空
+    pub(crate) fn book_source(base_url: &str, overrides: Value) -> BookSource {
+        let mut book_source = json!({
+            "bookSourceGroup": "",
+            "bookSourceName": "测试",
+            "bookSourceUrl": base_url,
+            "lastUpdateTime": 0,
+            "searchUrl": "/search",
+            "ruleSearch": { "bookList": "", "bookUrl": "", "name": "", "author": "" },
+            "ruleBookInfo": { "name": "", "author": "" },
+            "ruleToc": { "chapterList": "", "chapterName": "", "chapterUrl": "" },
+            "ruleContent": { "content": "" }
+        });
+        if let (Some(book_source), Value::Object(overrides)) =
+            (book_source.as_object_mut(), overrides)
+        {
+            book_source.extend(overrides);
         }
+        serde_json::from_value(book_source).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_search_books_post() {
+        let base_url = serve(|req| {
+            if req.method != "POST" || req.path != "/search" {
+                return TestResponse::status(404);
+            }
+            let body = String::from_utf8_lossy(&req.body).to_string();
+            TestResponse::ok(
+                json!({ "list": [{ "name": body, "author": "作者", "url": "/book/1" }] })
+                    .to_string(),
+            )
+        });
+
+        let book_source = book_source(
+            &base_url,
+            json!({
+                "searchUrl": "/search,{\"method\": \"POST\", \"body\": \"q={{key}}&page={{page}}\", \"charset\": \"gbk\"}",
+                "ruleSearch": {
+                    "bookList": "$.list[*]",
+                    "bookUrl": "$.url",
+                    "name": "$.name",
+                    "author": "$.author"
+                }
+            }),
+        );
+
+        let mut parser = BookSourceParser::new(book_source).unwrap();
+        let list = parser.search_books("斗破", 2, 20).await.unwrap();
+        assert_eq!(list.len(), 1);
+        assert_eq!(list[0].book_info.name, "q=%B6%B7%C6%C6&page=2");
+        assert_eq!(list[0].book_url, format!("{}/book/1", base_url));
+    }
+
+    #[tokio::test]
+    async fn test_search_books_post_json() {
+        let base_url = serve(|req| {
+            let body = String::from_utf8_lossy(&req.body).to_string();
+            TestResponse::ok(
+                json!({
+                    "list": [{
+                        "name": body,
+                        "author": req.headers.get("content-type").cloned().unwrap_or_default(),
+                        "url": req.path,
+                    }]
+                })
+                .to_string(),
+            )
+        });
+
+        let book_source = book_source(
+            &base_url,
+            json!({
+                "searchUrl": "/search?q={{key}}&b={{key@encode:base64}},{\"method\": \"POST\", \"body\": {\"key\": \"{{key}}\", \"page\": \"{{page}}\"}}",
+                "ruleSearch": {
+                    "bookList": "$.list[*]",
+                    "bookUrl": "$.url",
+                    "name": "$.name",
+                    "author": "$.author"
+                }
+            }),
+        );
+
+        let mut parser = BookSourceParser::new(book_source).unwrap();
+        let list = parser.search_books("斗\"破", 2, 20).await.unwrap();
+        // JSON 请求体中使用原始关键字
+        let body: Value = serde_json::from_str(&list[0].book_info.name).unwrap();
+        assert_eq!(body, json!({ "key": "斗\"破", "page": "2" }));
+        assert_eq!(list[0].book_info.author, "application/json");
+        // 链接中的 {{key}} 进行 url 编码，其他表达式使用原始关键字
+        assert!(list[0]
+            .book_url
+            .ends_with("/search?q=%E6%96%97%22%E7%A0%B4&b=5paXIuegtA=="));
+    }
+
+    #[test]
+    fn test_parser_without_runtime() {
+        let book_source = book_source(
+            "https://example.com",
+            json!({
+                "httpConfig": { "rateLimit": { "maxCount": 1, "fillDuration": 1.0 } }
+            }),
+        );
+
+        // 创建解析器不需要 tokio 运行时
+        let parser = BookSourceParser::new(book_source).unwrap();
+        assert!(parser.clone().http_client.rate_limit.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_login() {
+        let base_url = serve(|req| match (req.method.as_str(), req.path.as_str()) {
+            ("POST", "/login") => {
+                if String::from_utf8_lossy(&req.body) == "user=%E5%BC%A0%E4%B8%89&pass=123" {
+                    TestResponse {
+                        status: 200,
+                        headers: vec![("set-cookie".into(), "session=abc; Path=/".into())],
+                        body: "<p class=\"user\">张三</p>".into(),
+                    }
+                } else {
+                    TestResponse::ok("<p class=\"error\">密码错误</p>")
+                }
+            }
+            _ => TestResponse::ok(req.headers.get("cookie").cloned().unwrap_or_default()),
+        });
+
+        let book_source = book_source(
+            &base_url,
+            json!({
+                "loginUrl": "/login,{\"method\": \"POST\", \"body\": \"user={{username}}&pass={{password}}\"}",
+                "loginCheck": "@css:.user@text"
+            }),
+        );
+        let cookie_file = std::env::temp_dir()
+            .join(format!("login-test-{}", std::process::id()))
+            .join("cookies.json");
+        let cache_dir = cookie_file.with_file_name("cache");
+        std::fs::create_dir_all(&cache_dir).unwrap();
+
+        let mut parser = BookSourceParser::new(book_source.clone())
+            .unwrap()
+            .with_cookie_file(&cookie_file)
+            .unwrap()
+            .with_cache_dir(&cache_dir);
+        assert!(parser.need_login());
+        let err = parser.login("张三", "456").await.unwrap_err();
+        assert!(err.to_string().contains("登录失败"));
+        assert_eq!(err.diagnostic().unwrap().stage, Some(Stage::Login));
+
+        assert!(cache_dir.exists());
+        parser.login("张三", "123").await.unwrap();
+        assert_eq!(parser.http_client.cookie_count(), 1);
+        // 登录成功之后清空登录之前的缓存
+        assert!(!cache_dir.exists());
+
+        assert!(cookie_file.exists());
+
+        // 使用同一个文件的解析器共享 cookie
+        let other = BookSourceParser::new(book_source)
+            .unwrap()
+            .with_cookie_file(&cookie_file)
+            .unwrap();
+        assert_eq!(
+            other.http_client.get_text("/book/1").await.unwrap(),
+            "session=abc"
+        );
+
+        other.logout().unwrap();
+        assert!(!cookie_file.exists());
+        assert_eq!(parser.http_client.get_text("/book/1").await.unwrap(), "");
+        // cookie 没有变化时不会重新写入文件
+        assert!(!cookie_file.exists());
+        std::fs::remove_dir_all(cookie_file.parent().unwrap()).ok();
+    }
+
+    #[tokio::test]
+    async fn test_rule_diagnostic() {
+        let base_url =
+            serve(|_| TestResponse::ok(r#"<ul><li><a href="/1">第一章</a></li></ul>"#.to_string()));
+
+        let book_source = book_source(
+            &base_url,
+            json!({
+                "ruleToc": {
+                    "chapterList": "@css:li",
+                    "chapterName": "@css:a@text",
+                    "chapterUrl": "$.url"
+                }
+            }),
+        );
+
+        let mut parser = BookSourceParser::new(book_source).unwrap();
+        let err = parser.get_chapters("/toc").await.unwrap_err();
+        let diagnostic = err.diagnostic().unwrap();
+        assert_eq!(diagnostic.book_source.as_deref(), Some("测试"));
+        assert_eq!(diagnostic.stage, Some(Stage::Toc));
+        assert_eq!(diagnostic.field.as_deref(), Some("ruleToc.chapterUrl"));
+        assert_eq!(diagnostic.step.as_deref(), Some("$.url"));
+        assert_eq!(
+            diagnostic.input.as_deref(),
+            Some(r#"<li><a href="/1">第一章</a></li>"#)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_book_info_extra() {
+        let base_url = serve(|req| match req.path.as_str() {
+            "/book/42" => TestResponse::ok(
+                r#"<h1>书名</h1><p class="status">连载中</p><p class="tags">玄幻，系统 玄幻</p><p class="rating">8.7</p><p class="platform">起点</p>"#,
+            ),
+            _ => TestResponse::status(404),
+        });
+
+        let book_source = book_source(
+            &base_url,
+            json!({
+                "ruleBookInfo": {
+                    "name": "@css:h1@text",
+                    "author": "",
+                    "status": "@css:.status@text",
+                    "tags": "@css:.tags@text",
+                    "rating": "@css:.rating@text",
+                    "extra": { "平台": "@css:.platform@text", "字数": "@css:.words@text" }
+                }
+            }),
+        );
+        assert!(book_source
+            .rules()
+            .iter()
+            .any(|(field, _)| field == "ruleBookInfo.extra.平台"));
+
+        let mut parser = BookSourceParser::new(book_source).unwrap();
+        let book_info = parser.get_book_info("/book/42").await.unwrap();
+        assert_eq!(book_info.name, "书名");
+        assert_eq!(book_info.status, "连载中");
+        assert_eq!(book_info.tags, vec!["玄幻", "系统"]);
+        assert_eq!(book_info.rating, "8.7");
+        // 结果为空的其他信息不保留
+        assert_eq!(
+            book_info.extra,
+            BTreeMap::from([("平台".to_string(), "起点".to_string())])
+        );
+        assert!(BookFilter::parse("标签:系统 评分>8").matches(&book_info));
+    }
+
+    #[tokio::test]
+    async fn test_toc_url() {
+        let base_url = serve(|req| match req.path.as_str() {
+            "/book/42/" => TestResponse::ok(r#"<a href="/detail">详情页中的目录</a>"#),
+            "/book/42/toc.html" => TestResponse::ok(r#"<a href="1.html">第一章</a>"#),
+            _ => TestResponse::status(404),
+        });
+
+        let book_source = book_source(
+            &base_url,
+            json!({
+                "ruleToc": {
+                    "chapterList": "@css:a",
+                    "chapterName": "@css:a@text",
+                    "chapterUrl": "@css:a@href"
+                }
+            }),
+        );
+
+        let mut parser = BookSourceParser::new(book_source).unwrap();
+        parser.get_book_info("/book/42/").await.unwrap();
+
+        // tocUrl 为空时使用详情页
+        let chapters = parser.get_chapters("").await.unwrap();
+        assert_eq!(chapters[0].chapter_name, "详情页中的目录");
+
+        // 相对链接相对于详情页解析
+        let chapters = parser.get_chapters("toc.html").await.unwrap();
+        assert_eq!(chapters[0].chapter_name, "第一章");
+        assert_eq!(
+            chapters[0].chapter_url,
+            format!("{}/book/42/1.html", base_url)
+        );
+
+        let dir = std::env::temp_dir().join(format!("toc-url-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("toc.html");
+        std::fs::write(&file, r#"<a href="2.html">本地目录</a>"#).unwrap();
+        let chapters = parser
+            .get_chapters(&format!("file://{}", file.display()))
+            .await
+            .unwrap();
+        assert_eq!(chapters[0].chapter_name, "本地目录");
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_resolve_chapter_urls() {
+        let base_url = serve(|req| match req.path.as_str() {
+            "/book/42" => TestResponse {
+                status: 302,
+                headers: vec![("location".into(), "/book/42/".into())],
+                body: vec![],
+            },
+            "/book/42/" => TestResponse::ok(
+                r#"<ul><li class="volume">第一卷</li><li><a href="../123.html">第一章</a></li><li><a href="5.html">第二章</a><i>vip</i></li></ul>"#,
+            ),
+            _ => TestResponse::status(404),
+        });
+
+        let book_source = book_source(
+            &base_url,
+            json!({
+                "ruleToc": {
+                    "chapterList": "@css:li",
+                    "chapterName": "@css:li@text||@css:a@text",
+                    "chapterUrl": "@css:a@href",
+                    "isVolume": "@css:li.volume@text",
+                    "isVip": "@css:i@text"
+                }
+            }),
+        );
+
+        let mut parser = BookSourceParser::new(book_source).unwrap();
+        let chapters = parser.get_chapters("/book/42").await.unwrap();
+        assert!(chapters[0].is_volume);
+        assert_eq!(chapters[0].chapter_url, "");
+        // 相对于重定向之后的目录页解析
+        assert_eq!(
+            chapters[1].chapter_url,
+            format!("{}/book/123.html", base_url)
+        );
+        assert!(!chapters[1].is_volume && !chapters[1].is_vip);
+        assert_eq!(
+            chapters[2].chapter_url,
+            format!("{}/book/42/5.html", base_url)
+        );
+        assert!(chapters[2].is_vip);
+    }
+
+    #[tokio::test]
+    async fn test_toc_pagination() {
+        use std::sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        };
+
+        let count = Arc::new(AtomicUsize::new(0));
+        let base_url = serve({
+            let count = count.clone();
+            move |req| {
+                count.fetch_add(1, Ordering::SeqCst);
+                let chapters = match req.path.as_str() {
+                    "/toc?page=1" | "/last?page=1" => vec!["/c/11", "/c/12"],
+                    "/toc?page=2" | "/last?page=2" => vec!["/c/12", "/c/21"],
+                    "/toc?page=3" => vec!["/c/31"],
+                    // 超过最后一页时一直返回最后一页
+                    path if path.starts_with("/last?") => vec!["/c/12", "/c/21"],
+                    _ => return TestResponse::status(404),
+                };
+                let page = &req.path[req.path.len() - 1..];
+                // 最后一页的下一页回到第一页
+                let next = page.parse::<usize>().unwrap() % 3 + 1;
+                let list = chapters
+                    .iter()
+                    .map(|url| format!(r#"<li><a href="{}">{}</a></li>"#, url, url))
+                    .collect::<String>();
+                TestResponse::ok(format!(
+                    r#"<span id="count">2</span><ul>{}</ul><a id="next" href="?page={}">下一页</a>"#,
+                    list, next
+                ))
+            }
+        });
+
+        let toc_source = |rule_toc: Value| book_source(&base_url, json!({ "ruleToc": rule_toc }));
+        let urls = |chapters: Vec<Chapter>| {
+            chapters
+                .into_iter()
+                .map(|chapter| chapter.chapter_url.replace(&base_url, ""))
+                .collect::<Vec<_>>()
+        };
+
+        // 回到第一页时停止，按链接去重后倒序
+        let mut parser = BookSourceParser::new(toc_source(json!({
+            "chapterList": "@css:li",
+            "chapterName": "@css:a@text",
+            "chapterUrl": "@css:a@href",
+            "nextTocUrl": "@css:#next@href",
+            "reverse": true,
+            "dedup": true
+        })))
+        .unwrap();
+        let chapters = parser.get_chapters("/toc?page=1").await.unwrap();
+        assert_eq!(urls(chapters), vec!["/c/31", "/c/21", "/c/12", "/c/11"]);
+
+        // 页数限制请求的页数，链接模板可以使用下一页的页码
+        let mut parser = BookSourceParser::new(toc_source(json!({
+            "chapterList": "@css:li",
+            "chapterName": "@css:a@text",
+            "chapterUrl": "@css:a@href",
+            "nextTocUrl": "/toc?page={{page}}",
+            "pageCount": "@css:#count@text"
+        })))
+        .unwrap();
+        let chapters = parser.get_chapters("/toc?page=1").await.unwrap();
+        assert_eq!(urls(chapters), vec!["/c/11", "/c/12", "/c/12", "/c/21"]);
+
+        // 没有页数限制的链接模板，请求失败时停止
+        let rule_toc = json!({
+            "chapterList": "@css:li",
+            "chapterName": "@css:a@text",
+            "chapterUrl": "@css:a@href",
+            "nextTocUrl": "/toc?page={{page}}"
+        });
+        let mut parser = BookSourceParser::new(toc_source(rule_toc.clone())).unwrap();
+        count.store(0, Ordering::SeqCst);
+        let chapters = parser.get_chapters("/toc?page=1").await.unwrap();
+        assert_eq!(
+            urls(chapters),
+            vec!["/c/11", "/c/12", "/c/12", "/c/21", "/c/31"]
+        );
+        assert_eq!(count.load(Ordering::SeqCst), 4);
+
+        // 某一页没有新章节时停止
+        let mut rule_toc = rule_toc;
+        rule_toc["nextTocUrl"] = json!("/last?page={{page}}");
+        let mut parser = BookSourceParser::new(toc_source(rule_toc)).unwrap();
+        count.store(0, Ordering::SeqCst);
+        let chapters = parser.get_chapters("/last?page=1").await.unwrap();
+        assert_eq!(urls(chapters), vec!["/c/11", "/c/12", "/c/12", "/c/21"]);
+        assert_eq!(count.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_content_follow() {
+        let base_url = serve(|req| {
+            let (text, next) = match req.path.as_str() {
+                "/book/1/123.html" => ("第一页", "123_2.html"),
+                "/book/1/123_2.html" => ("第二页", "123_3.html"),
+                // 最后一页的下一页是下一章
+                "/book/1/123_3.html" => ("第三页", "124.html"),
+                "/book/1/124.html" => ("下一章", "124_2.html"),
+                // 下一页回到本页
+                "/book/1/124_2.html" => ("下一章第二页", "124_2.html"),
+                _ => return TestResponse::status(404),
+            };
+            TestResponse::ok(format!(
+                r#"<div id="content">{}</div><a id="next" href="{}">下一页</a>"#,
+                text, next
+            ))
+        });
+
+        let content_source =
+            |rule_content: Value| book_source(&base_url, json!({ "ruleContent": rule_content }));
+
+        let mut parser = BookSourceParser::new(content_source(json!({
+            "content": "@css:#content@text",
+            "nextContentUrl": "@css:#next@href"
+        })))
+        .unwrap();
+        assert!(matches!(
+            parser.book_source.rule_content,
+            RuleContent::Follow { .. }
+        ));
+        assert_eq!(
+            parser.get_content("/book/1/123.html").await.unwrap(),
+            "第一页\n第二页\n第三页"
+        );
+        assert_eq!(
+            parser.get_content("/book/1/124.html").await.unwrap(),
+            "下一章\n下一章第二页"
+        );
+
+        let mut parser = BookSourceParser::new(content_source(json!({
+            "content": "@css:#content@text",
+            "nextContentUrl": "@css:#next@href",
+            "maxPages": 2
+        })))
+        .unwrap();
+        assert_eq!(
+            parser.get_content("/book/1/123.html").await.unwrap(),
+            "第一页\n第二页"
+        );
     }
 }
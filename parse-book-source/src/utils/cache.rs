@@ -0,0 +1,54 @@
+//! 编译结果的缓存，规则在书源加载时编译一次，之后解析时直接复用
+
+use std::{collections::HashMap, sync::RwLock};
+
+/// 缓存条目的上限，超过后清空，避免模板渲染出的规则无限增长
+const CAPACITY: usize = 4096;
+
+#[derive(Debug, Default)]
+pub struct Cache<T> {
+    map: RwLock<HashMap<String, T>>,
+}
+
+impl<T: Clone> Cache<T> {
+    pub fn new() -> Self {
+        Self {
+            map: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// 获取缓存，不存在时编译并缓存，编译失败时不缓存
+    pub fn get_or_try_insert<E>(
+        &self,
+        key: &str,
+        f: impl FnOnce() -> Result<T, E>,
+    ) -> Result<T, E> {
+        if let Some(value) = self.map.read().ok().and_then(|map| map.get(key).cloned()) {
+            return Ok(value);
+        }
+
+        let value = f()?;
+        if let Ok(mut map) = self.map.write() {
+            if map.len() >= CAPACITY {
+                map.clear();
+            }
+            map.insert(key.to_string(), value.clone());
+        }
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache() {
+        let cache = Cache::new();
+        assert_eq!(cache.get_or_try_insert("a", || Ok::<_, ()>(1)), Ok(1));
+        // 已缓存时不再编译
+        assert_eq!(cache.get_or_try_insert("a", || Err(())), Ok(1));
+        assert_eq!(cache.get_or_try_insert("b", || Err::<i32, _>(())), Err(()));
+        assert_eq!(cache.get_or_try_insert("b", || Ok::<_, ()>(2)), Ok(2));
+    }
+}
@@ -2,7 +2,17 @@ use crate::Result;
 use jsonpath_rust::JsonPath;
 use regex::{Captures, Regex};
 use serde_json::Value;
-use std::str::FromStr;
+use std::{str::FromStr, sync::LazyLock};
+
+pub mod cache;
+pub mod url;
+pub use cache::Cache;
+pub use url::{resolve_url, same_chapter};
+#[cfg(test)]
+pub(crate) mod test_server;
+
+static JSON_PATHS: LazyLock<Cache<JsonPath>> = LazyLock::new(Cache::new);
+static REGEXES: LazyLock<Cache<Regex>> = LazyLock::new(Cache::new);
 
 pub fn replace_all(
     re: &Regex,
@@ -21,6 +31,16 @@ pub fn replace_all(
     Ok(new)
 }
 
+/// 编译 JSONPath，结果会被缓存
+pub fn compile_json_path(path: &str) -> Result<JsonPath> {
+    Ok(JSON_PATHS.get_or_try_insert(path, || JsonPath::from_str(path))?)
+}
+
+/// 编译正则表达式，结果会被缓存
+pub fn compile_regex(pattern: &str) -> Result<Regex> {
+    Ok(REGEXES.get_or_try_insert(pattern, || Regex::new(pattern))?)
+}
+
 pub fn json_path(data: &Value, path: &str) -> Result<Value> {
-    Ok(JsonPath::from_str(path)?.find(data))
+    Ok(compile_json_path(path)?.find(data))
 }
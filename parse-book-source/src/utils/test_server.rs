@@ -0,0 +1,108 @@
+//! 测试用的简易 HTTP 服务器，每个连接只处理一个请求
+
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader, Read, Write},
+    net::TcpListener,
+    thread,
+};
+
+#[derive(Debug, Clone)]
+pub struct TestRequest {
+    pub method: String,
+    pub path: String,
+    /// 请求头名称为小写
+    pub headers: HashMap<String, String>,
+    pub body: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TestResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl TestResponse {
+    pub fn ok<T: Into<Vec<u8>>>(body: T) -> Self {
+        Self {
+            status: 200,
+            headers: vec![],
+            body: body.into(),
+        }
+    }
+
+    pub fn status(status: u16) -> Self {
+        Self {
+            status,
+            headers: vec![],
+            body: vec![],
+        }
+    }
+}
+
+/// 启动服务器，返回 `http://127.0.0.1:port`
+pub fn serve<F>(handler: F) -> String
+where
+    F: Fn(&TestRequest) -> TestResponse + Send + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else {
+                continue;
+            };
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+            let mut request_line = String::new();
+            if reader.read_line(&mut request_line).is_err() {
+                continue;
+            }
+            let mut parts = request_line.split_whitespace();
+            let method = parts.next().unwrap_or_default().to_string();
+            let path = parts.next().unwrap_or_default().to_string();
+
+            let mut headers = HashMap::new();
+            loop {
+                let mut line = String::new();
+                if reader.read_line(&mut line).is_err() || line.trim().is_empty() {
+                    break;
+                }
+                if let Some((k, v)) = line.split_once(':') {
+                    headers.insert(k.trim().to_lowercase(), v.trim().to_string());
+                }
+            }
+
+            let length = headers
+                .get("content-length")
+                .and_then(|l| l.parse::<usize>().ok())
+                .unwrap_or(0);
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).ok();
+
+            let response = handler(&TestRequest {
+                method,
+                path,
+                headers,
+                body,
+            });
+
+            let mut head = format!(
+                "HTTP/1.1 {} OK\r\ncontent-length: {}\r\nconnection: close\r\n",
+                response.status,
+                response.body.len()
+            );
+            for (k, v) in response.headers {
+                head.push_str(&format!("{}: {}\r\n", k, v));
+            }
+            head.push_str("\r\n");
+
+            stream.write_all(head.as_bytes()).ok();
+            stream.write_all(&response.body).ok();
+        }
+    });
+
+    format!("http://{}", addr)
+}
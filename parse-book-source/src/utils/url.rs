@@ -0,0 +1,127 @@
+use reqwest::Url;
+
+/// 按 RFC 3986 把链接解析为相对于 `base` 的绝对链接，例如 `../123.html`、`5.html`
+/// 链接后面的请求选项（`,{...}`）保持不变，空链接、模板、脚本和无法解析的链接原样返回
+pub fn resolve_url(base: &str, url: &str) -> String {
+    let url = url.trim();
+    if url.is_empty() || url.contains("{{") || url.contains("<js>") || url.contains("@js:") {
+        return url.to_string();
+    }
+
+    let (link, options) = match url.find(",{") {
+        Some(index) => url.split_at(index),
+        None => (url, ""),
+    };
+
+    match Url::parse(base).and_then(|base| base.join(link)) {
+        Ok(link) => format!("{}{}", link, options),
+        Err(_) => url.to_string(),
+    }
+}
+
+/// 判断下一页的链接是否还属于同一章，例如 `123.html` 的下一页 `123_2.html`、`123/2.html`
+/// 或者 `read?id=123` 的下一页 `read?id=123&page=2`
+///
+/// 去掉扩展名之后，下一页的路径以本章的路径开头，并且后面不是字母或数字；
+/// 路径相同时，本章链接的查询参数都要出现在下一页的链接中
+pub fn same_chapter(chapter_url: &str, next_url: &str) -> bool {
+    let (Ok(chapter), Ok(next)) = (Url::parse(chapter_url), Url::parse(next_url)) else {
+        return false;
+    };
+    if chapter.host_str() != next.host_str() {
+        return false;
+    }
+
+    let stem = |url: &Url| {
+        let path = url.path().trim_end_matches('/');
+        match path.rfind(['.', '/']) {
+            Some(index) if path[index..].starts_with('.') => path[..index].to_string(),
+            _ => path.to_string(),
+        }
+    };
+    match stem(&next).strip_prefix(&stem(&chapter)) {
+        Some("") => {
+            let pairs = next.query_pairs().collect::<Vec<_>>();
+            chapter.query_pairs().all(|pair| pairs.contains(&pair))
+        }
+        Some(rest) => !rest.starts_with(char::is_alphanumeric),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_url() {
+        let base = "https://example.com/book/42/";
+        assert_eq!(
+            resolve_url(base, "../123.html"),
+            "https://example.com/book/123.html"
+        );
+        assert_eq!(
+            resolve_url(base, "5.html"),
+            "https://example.com/book/42/5.html"
+        );
+        assert_eq!(
+            resolve_url("https://example.com/book/42/index.html", "5.html"),
+            "https://example.com/book/42/5.html"
+        );
+        assert_eq!(resolve_url(base, "/1.html"), "https://example.com/1.html");
+        assert_eq!(
+            resolve_url(base, "//cdn.example.com/a.jpg"),
+            "https://cdn.example.com/a.jpg"
+        );
+        assert_eq!(
+            resolve_url(base, "https://other.com/a"),
+            "https://other.com/a"
+        );
+        assert_eq!(
+            resolve_url("https://example.com", "a"),
+            "https://example.com/a"
+        );
+        assert_eq!(
+            resolve_url(base, r#"toc,{"method":"POST"}"#),
+            r#"https://example.com/book/42/toc,{"method":"POST"}"#
+        );
+        assert_eq!(resolve_url(base, ""), "");
+        assert_eq!(resolve_url(base, "/s?p={{page}}"), "/s?p={{page}}");
+    }
+
+    #[test]
+    fn test_same_chapter() {
+        let chapter = "https://example.com/book/1/123.html";
+        assert!(same_chapter(
+            chapter,
+            "https://example.com/book/1/123_2.html"
+        ));
+        assert!(same_chapter(
+            chapter,
+            "https://example.com/book/1/123/2.html"
+        ));
+        assert!(!same_chapter(
+            chapter,
+            "https://example.com/book/1/124.html"
+        ));
+        assert!(!same_chapter(
+            "https://example.com/book/1/12.html",
+            "https://example.com/book/1/123.html"
+        ));
+        assert!(!same_chapter(
+            chapter,
+            "https://other.com/book/1/123_2.html"
+        ));
+
+        let chapter = "https://example.com/read.php?id=123";
+        assert!(same_chapter(
+            chapter,
+            "https://example.com/read.php?id=123&page=2"
+        ));
+        assert!(!same_chapter(
+            chapter,
+            "https://example.com/read.php?id=124"
+        ));
+        assert!(!same_chapter(chapter, "/read.php?id=123&page=2"));
+    }
+}
@@ -0,0 +1,68 @@
+use crate::{utils::novel_catch_dir, Result};
+use anyhow::anyhow;
+use parse_book_source::{BookSource, BookSourceParser, HttpConfig};
+use std::{fs::File, path::PathBuf, sync::OnceLock};
+
+/// 全局的网络设置，保存在 `http_config.json` 中，例如代理、域名解析和证书，
+/// 书源没有设置的选项使用这里的配置，启动时通过 [load_http_config] 读取
+static HTTP_CONFIG: OnceLock<HttpConfig> = OnceLock::new();
+
+/// 读取全局网络设置，文件不存在时使用默认配置，格式错误时返回错误
+pub fn load_http_config() -> Result<&'static HttpConfig> {
+    if let Some(config) = HTTP_CONFIG.get() {
+        return Ok(config);
+    }
+
+    let path = novel_catch_dir()?.join("http_config.json");
+    let config = match File::open(&path) {
+        Ok(file) => serde_json::from_reader(file)
+            .map_err(|e| anyhow!("网络设置 {} 格式错误: {}", path.display(), e))?,
+        Err(_) => HttpConfig::default(),
+    };
+    Ok(HTTP_CONFIG.get_or_init(|| config))
+}
+
+/// 全局网络设置，还没有读取时使用默认配置
+pub fn http_config() -> &'static HttpConfig {
+    HTTP_CONFIG.get_or_init(HttpConfig::default)
+}
+
+/// 书源的标识，按书源链接和名称区分
+fn source_key(book_source: &BookSource) -> String {
+    let key = format!(
+        "{}\n{}",
+        book_source.book_source_url, book_source.book_source_name
+    );
+    let hash = key.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+    format!("{:016x}", hash)
+}
+
+/// 书源的 cookie 文件，保存在 `cookies` 目录中
+pub fn cookie_file(book_source: &BookSource) -> Result<PathBuf> {
+    Ok(novel_catch_dir()?
+        .join("cookies")
+        .join(format!("{}.json", source_key(book_source))))
+}
+
+/// 书源的响应缓存目录，每个书源一个目录，登录和退出登录时只清空这个书源的缓存
+pub fn http_cache_dir(book_source: &BookSource) -> Result<PathBuf> {
+    Ok(novel_catch_dir()?
+        .join("http_cache")
+        .join(source_key(book_source)))
+}
+
+/// 创建书源解析器，使用全局网络设置和书源共享的 cookie，
+/// 全局配置或者书源设置了 `cache` 时才缓存响应
+pub fn book_source_parser(book_source: BookSource) -> Result<BookSourceParser> {
+    let cookie_file = cookie_file(&book_source)?;
+    let cache_dir = http_cache_dir(&book_source)?;
+    let parser = BookSourceParser::with_http_defaults(book_source, http_config())?
+        .with_cookie_file(cookie_file)?;
+    if parser.http_client.config.cache.is_some() {
+        Ok(parser.with_cache_dir(cache_dir))
+    } else {
+        Ok(parser)
+    }
+}
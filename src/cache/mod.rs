@@ -5,5 +5,7 @@ pub use network_novel::NetworkNovelCache;
 pub mod book_source;
 pub mod history;
 pub use history::*;
+pub mod http_setting;
+pub use http_setting::*;
 pub mod setting;
 pub use setting::*;
@@ -24,7 +24,16 @@ impl Warning {
 
 impl Widget for Warning {
     fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer) {
-        let [vertical] = Layout::vertical([Constraint::Length(8)])
+        // 书源规则错误等多行信息按内容计算高度，左对齐显示
+        let multi_line = self.tip.lines().count() > 1;
+        let width = (area.width * 7 / 10).saturating_sub(8).max(1) as usize;
+        let lines = self
+            .tip
+            .lines()
+            .map(|line| Line::from(line).width().div_ceil(width).max(1))
+            .sum::<usize>() as u16;
+
+        let [vertical] = Layout::vertical([Constraint::Length(lines.saturating_add(6).max(8))])
             .flex(Flex::Center)
             .areas(area);
         let [horizontal] = Layout::horizontal([Constraint::Percentage(70)])
@@ -59,8 +68,14 @@ impl Widget for Warning {
 
         Block::new().dim().render(area, buf);
 
-        Paragraph::new(self.tip)
-            .centered()
+        let paragraph = Paragraph::new(self.tip);
+        let paragraph = if multi_line {
+            paragraph.left_aligned()
+        } else {
+            paragraph.centered()
+        };
+
+        paragraph
             .style(if self.is_error {
                 THEME_CONFIG.error_modal.text
             } else {
@@ -19,6 +19,7 @@ pub mod pages;
 pub mod quick_start;
 pub mod router;
 pub mod routes;
+pub mod source_check;
 pub mod utils;
 
 pub use cache::*;
@@ -42,6 +43,35 @@ where
         return Ok(());
     }
 
+    let http_config = match load_http_config() {
+        Ok(http_config) => http_config,
+        Err(e) => {
+            eprintln!("{}: {}", "Error".red().bold(), e.to_string().red());
+            return Ok(());
+        }
+    };
+    if let Some(max) = http_config.max_concurrency {
+        parse_book_source::rate_limiter::set_max_concurrency(max);
+    }
+
+    if let Some(Commands::Source {
+        command:
+            SourceCommands::Check {
+                name,
+                keyword,
+                record,
+                replay,
+            },
+    }) = trnovel.subcommand
+    {
+        let cassette = match (record, replay) {
+            (Some(dir), _) => Some(source_check::Cassette::Record(dir)),
+            (_, Some(dir)) => Some(source_check::Cassette::Replay(dir)),
+            _ => None,
+        };
+        return source_check::check_book_sources(name, &keyword, cassette).await;
+    }
+
     let terminal = ratatui::init();
     //支持鼠标事件，需要开启鼠标捕获 https://docs.rs/crossterm/0.28.1/crossterm/event/index.html
     execute!(stdout(), EnableMouseCapture)?;
@@ -117,4 +147,31 @@ pub enum Commands {
     /// 历史记录模式，查看阅读记录
     #[command(short_flag = 'H')]
     History,
+
+    /// 书源工具
+    Source {
+        #[command(subcommand)]
+        command: SourceCommands,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum SourceCommands {
+    /// 检查书源是否可用：发现 → 搜索 → 详情 → 目录 → 正文
+    Check {
+        /// 书源名称，默认检查全部书源
+        name: Option<String>,
+
+        /// 搜索关键字
+        #[arg(short, long, default_value = "斗破苍穹")]
+        keyword: String,
+
+        /// 把请求和响应录制到指定目录，用于离线测试或者反馈问题
+        #[arg(long, value_name = "DIR")]
+        record: Option<PathBuf>,
+
+        /// 回放指定目录中录制的响应，不访问网络
+        #[arg(long, value_name = "DIR", conflicts_with = "record")]
+        replay: Option<PathBuf>,
+    },
 }
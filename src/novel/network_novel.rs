@@ -1,6 +1,9 @@
-use super::{Novel, NovelChapters};
+use super::{ChapterKind, Novel, NovelChapters};
 use crate::{
-    book_source::BookSourceCache, cache::NetworkNovelCache, errors::Errors, history::HistoryItem,
+    book_source::BookSourceCache,
+    cache::{book_source_parser, NetworkNovelCache},
+    errors::Errors,
+    history::HistoryItem,
     Result,
 };
 use anyhow::anyhow;
@@ -35,7 +38,7 @@ impl NetworkNovel {
 
         let novel = NetworkNovel {
             book_list_item: network_cache.book_list_item,
-            book_source: Arc::new(Mutex::new(BookSourceParser::try_from(json_source)?)),
+            book_source: Arc::new(Mutex::new(book_source_parser(json_source)?)),
             book_info: None,
             novel_chapters: NovelChapters {
                 current_chapter: network_cache.current_chapter,
@@ -82,6 +85,18 @@ impl Novel for NetworkNovel {
         Ok(args)
     }
 
+    fn chapter_kind(&self, index: usize) -> ChapterKind {
+        match self
+            .chapters
+            .as_ref()
+            .and_then(|chapters| chapters.get(index))
+        {
+            Some(chapter) if chapter.is_volume => ChapterKind::Volume,
+            Some(chapter) if chapter.is_vip => ChapterKind::Vip,
+            _ => ChapterKind::Normal,
+        }
+    }
+
     fn get_current_chapter_name(&self) -> Result<String> {
         self.get_current_chapter()
             .map(|chapter| chapter.chapter_name)
@@ -123,6 +138,12 @@ impl Novel for NetworkNovel {
         let book_source = self.book_source.clone();
         let chapter = self.get_current_chapter()?;
 
+        // 分卷标题没有正文，直接显示标题
+        if chapter.is_volume {
+            callback(Ok(chapter.chapter_name));
+            return Ok(());
+        }
+
         tokio::spawn(async move {
             let res = book_source
                 .lock()
@@ -24,6 +24,17 @@ where
     }
 }
 
+/// 章节在目录中的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChapterKind {
+    #[default]
+    Normal,
+    /// 分卷标题，没有正文，翻页时跳过
+    Volume,
+    /// 需要付费的章节，目录和阅读页的标题中会标记出来
+    Vip,
+}
+
 #[async_trait]
 pub trait Novel: Deref<Target = NovelChapters<Self::Chapter>> + DerefMut + Sized {
     type Chapter: Sync + Send + Clone;
@@ -33,6 +44,18 @@ pub trait Novel: Deref<Target = NovelChapters<Self::Chapter>> + DerefMut + Sized
 
     fn set_chapters(&mut self, chapters: &[Self::Chapter]) {
         self.chapters = Some(chapters.to_vec());
+
+        // 当前章节是分卷标题时从下一章开始阅读，后面没有章节时退回上一章
+        if self.chapter_kind(self.current_chapter) == ChapterKind::Volume
+            && self.next_chapter().is_err()
+        {
+            self.prev_chapter().ok();
+        }
+    }
+
+    /// 章节的类型，默认都是普通章节
+    fn chapter_kind(&self, _index: usize) -> ChapterKind {
+        ChapterKind::Normal
     }
 
     fn get_current_chapter(&self) -> Result<Self::Chapter> {
@@ -58,12 +81,12 @@ pub trait Novel: Deref<Target = NovelChapters<Self::Chapter>> + DerefMut + Sized
     }
 
     fn next_chapter(&mut self) -> Result<()> {
-        if self.current_chapter + 1 >= self.get_chapters_result()?.len() {
-            Err("已经是最后一章了".into())
-        } else {
-            self.current_chapter += 1;
-            Ok(())
-        }
+        let len = self.get_chapters_result()?.len();
+        let next = (self.current_chapter + 1..len)
+            .find(|index| self.chapter_kind(*index) != ChapterKind::Volume)
+            .ok_or("已经是最后一章了")?;
+        self.current_chapter = next;
+        Ok(())
     }
 
     fn set_chapter(&mut self, chapter: usize) -> Result<()> {
@@ -78,12 +101,12 @@ pub trait Novel: Deref<Target = NovelChapters<Self::Chapter>> + DerefMut + Sized
     }
 
     fn prev_chapter(&mut self) -> Result<()> {
-        if self.current_chapter == 0 {
-            Err("已经是第一章了".into())
-        } else {
-            self.current_chapter -= 1;
-            Ok(())
-        }
+        let prev = (0..self.current_chapter)
+            .rev()
+            .find(|index| self.chapter_kind(*index) != ChapterKind::Volume)
+            .ok_or("已经是第一章了")?;
+        self.current_chapter = prev;
+        Ok(())
     }
     // 下面的逻辑需要根据Self::Chapter实现,上面的是通用的，可以直接使用NovelChapters的方法
 
@@ -99,7 +122,151 @@ pub trait Novel: Deref<Target = NovelChapters<Self::Chapter>> + DerefMut + Sized
 
     fn get_current_chapter_name(&self) -> Result<String>;
 
+    /// 阅读页显示的章节标题，需要付费的章节加上 `[VIP]` 标记
+    fn get_current_chapter_title(&self) -> Result<String> {
+        let name = self.get_current_chapter_name()?;
+        Ok(match self.chapter_kind(self.current_chapter) {
+            ChapterKind::Vip => format!("{} [VIP]", name),
+            _ => name,
+        })
+    }
+
     fn to_history_item(&self) -> Result<HistoryItem>;
 
     fn get_id(&self) -> String;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 章节为 (名称, 类型) 的测试小说
+    struct TestNovel(NovelChapters<(String, ChapterKind)>);
+
+    impl Deref for TestNovel {
+        type Target = NovelChapters<(String, ChapterKind)>;
+
+        fn deref(&self) -> &Self::Target {
+            &self.0
+        }
+    }
+
+    impl DerefMut for TestNovel {
+        fn deref_mut(&mut self) -> &mut Self::Target {
+            &mut self.0
+        }
+    }
+
+    #[async_trait]
+    impl Novel for TestNovel {
+        type Chapter = (String, ChapterKind);
+        type Args = ();
+
+        async fn init(_: Self::Args) -> Result<Self> {
+            Ok(Self(NovelChapters::new()))
+        }
+
+        fn chapter_kind(&self, index: usize) -> ChapterKind {
+            self.get_chapters()
+                .and_then(|chapters| chapters.get(index))
+                .map(|(_, kind)| *kind)
+                .unwrap_or_default()
+        }
+
+        fn get_chapters_names(&self) -> Result<Vec<(String, usize)>> {
+            Ok(self
+                .get_chapters_result()?
+                .iter()
+                .enumerate()
+                .map(|(index, (name, _))| (name.clone(), index))
+                .collect())
+        }
+
+        fn get_content<T: FnMut(Result<String>) + Send + 'static>(
+            &mut self,
+            mut callback: T,
+        ) -> Result<()> {
+            callback(self.get_current_chapter_name());
+            Ok(())
+        }
+
+        fn request_chapters<T: FnMut(Result<Vec<Self::Chapter>>) + Send + 'static>(
+            &self,
+            mut callback: T,
+        ) -> Result<()> {
+            callback(self.get_chapters_result().cloned());
+            Ok(())
+        }
+
+        fn get_current_chapter_name(&self) -> Result<String> {
+            Ok(self.get_current_chapter()?.0)
+        }
+
+        fn to_history_item(&self) -> Result<HistoryItem> {
+            Err("测试小说没有历史记录".into())
+        }
+
+        fn get_id(&self) -> String {
+            "test".to_string()
+        }
+    }
+
+    fn novel(current_chapter: usize, chapters: &[(&str, ChapterKind)]) -> TestNovel {
+        let mut novel = TestNovel(NovelChapters {
+            current_chapter,
+            ..NovelChapters::new()
+        });
+        let chapters = chapters
+            .iter()
+            .map(|(name, kind)| (name.to_string(), *kind))
+            .collect::<Vec<_>>();
+        novel.set_chapters(&chapters);
+        novel
+    }
+
+    #[test]
+    fn test_skip_volume() {
+        use ChapterKind::*;
+        let chapters = [
+            ("第一卷", Volume),
+            ("第一章", Normal),
+            ("第二卷", Volume),
+            ("第二章", Vip),
+            ("第三卷", Volume),
+        ];
+
+        // 开头的分卷标题从下一章开始阅读
+        let mut novel = novel(0, &chapters);
+        assert_eq!(novel.current_chapter, 1);
+        assert!(novel.prev_chapter().is_err());
+        assert_eq!(novel.current_chapter, 1);
+
+        // 翻页跳过中间的分卷标题
+        novel.next_chapter().unwrap();
+        assert_eq!(novel.current_chapter, 3);
+        novel.prev_chapter().unwrap();
+        assert_eq!(novel.current_chapter, 1);
+
+        // 结尾的分卷标题不算下一章
+        novel.set_chapter(3).unwrap();
+        assert!(novel.next_chapter().is_err());
+        assert_eq!(novel.current_chapter, 3);
+
+        // 停在结尾的分卷标题时退回上一章
+        let novel = self::novel(4, &chapters);
+        assert_eq!(novel.current_chapter, 3);
+
+        // 只有分卷标题时保持不变
+        let novel = self::novel(0, &[("第一卷", Volume)]);
+        assert_eq!(novel.current_chapter, 0);
+    }
+
+    #[test]
+    fn test_vip_title() {
+        use ChapterKind::*;
+        let mut novel = novel(0, &[("第一章", Normal), ("第二章", Vip)]);
+        assert_eq!(novel.get_current_chapter_title().unwrap(), "第一章");
+        novel.next_chapter().unwrap();
+        assert_eq!(novel.get_current_chapter_title().unwrap(), "第二章 [VIP]");
+    }
+}
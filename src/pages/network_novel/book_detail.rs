@@ -27,40 +27,35 @@ pub struct BookDetail {
 
 impl BookDetail {
     pub fn new(book_info: BookInfo, navigator: Navigator, mut novel: NetworkNovel) -> Self {
-        let title = vec![
-            Span::from("名称：").style(THEME_CONFIG.detail_info),
-            Span::from(book_info.name.clone()).style(THEME_CONFIG.basic.text),
+        let mut items = vec![
+            ("名称".to_string(), book_info.name.clone()),
+            ("作者".to_string(), book_info.author.clone()),
+            ("类型".to_string(), book_info.kind.clone()),
+            ("字数".to_string(), book_info.word_count.clone()),
+            ("最新章节".to_string(), book_info.last_chapter.clone()),
         ];
-        let author = vec![
-            Span::from("作者：").style(THEME_CONFIG.detail_info),
-            Span::from(book_info.author.clone()).style(THEME_CONFIG.basic.text),
-        ];
-        let kind = vec![
-            Span::from("类型：").style(THEME_CONFIG.detail_info),
-            Span::from(book_info.kind.clone()).style(THEME_CONFIG.basic.text),
-        ];
-        let word_count = vec![
-            Span::from("字数：").style(THEME_CONFIG.detail_info),
-            Span::from(book_info.word_count.clone()).style(THEME_CONFIG.basic.text),
-        ];
-
-        let last_chapter = vec![
-            Span::from("最新章节：").style(THEME_CONFIG.detail_info),
-            Span::from(book_info.last_chapter.clone()).style(THEME_CONFIG.basic.text),
-        ];
-
-        let text = vec![
-            Line::from(title),
-            Line::from(""),
-            Line::from(author),
-            Line::from(""),
-            Line::from(kind),
-            Line::from(""),
-            Line::from(word_count),
-            Line::from(""),
-            Line::from(last_chapter),
-            Line::from(""),
+        // 书源没有提供的信息不显示
+        let optional = [
+            ("状态".to_string(), book_info.status.clone()),
+            ("更新时间".to_string(), book_info.update_time.clone()),
+            ("标签".to_string(), book_info.tags.join("、")),
+            ("评分".to_string(), book_info.rating.clone()),
         ];
+        items.extend(
+            optional
+                .into_iter()
+                .chain(book_info.extra.clone())
+                .filter(|(_, value)| !value.is_empty()),
+        );
+
+        let mut text = vec![];
+        for (label, value) in items {
+            text.push(Line::from(vec![
+                Span::from(format!("{}：", label)).style(THEME_CONFIG.detail_info),
+                Span::from(value).style(THEME_CONFIG.basic.text),
+            ]));
+            text.push(Line::from(""));
+        }
 
         let paragraph = Paragraph::new(text).wrap(Wrap { trim: true });
 
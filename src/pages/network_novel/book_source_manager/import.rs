@@ -9,11 +9,11 @@ use crate::{
 };
 use async_trait::async_trait;
 use crossterm::event::{KeyCode, KeyEvent, KeyEventKind};
-use parse_book_source::BookSource;
+use parse_book_source::{BookSource, ImportReport, ImportWarning};
 use ratatui::{
     layout::{Constraint, Layout},
     text::{Line, Span, Text},
-    widgets::{Block, Clear, Padding, Paragraph, Scrollbar, ScrollbarState, Widget},
+    widgets::{Block, Clear, Padding, Paragraph, Scrollbar, ScrollbarState, Widget, Wrap},
 };
 use tui_widget_list::{ListBuilder, ListState, ListView};
 
@@ -79,6 +79,10 @@ pub struct Import {
     pub selected: HashSet<usize>,
     pub list_state: ListState,
     pub search: Search<'static>,
+    /// 转换报告，记录无法转换的字段和解析失败的书源
+    pub warnings: Vec<ImportWarning>,
+    pub show_report: bool,
+    pub report_scroll: u16,
 }
 
 impl Import {
@@ -100,6 +104,9 @@ impl Import {
             book_sources: vec![],
             selected: HashSet::new(),
             list_state: ListState::default(),
+            warnings: vec![],
+            show_report: false,
+            report_scroll: 0,
         }
     }
 
@@ -107,12 +114,41 @@ impl Import {
         self.is_loading = loading;
     }
 
-    pub fn set_book_sources(&mut self, book_sources: Vec<BookSource>) {
-        self.book_sources = book_sources;
+    pub fn set_report(&mut self, report: ImportReport) {
+        self.book_sources = report.book_sources;
+        self.warnings = report.warnings;
+        self.show_report = !self.warnings.is_empty();
+        self.report_scroll = 0;
         self.list_state.select(None);
         self.selected.clear();
     }
 
+    fn render_report(&mut self, frame: &mut ratatui::Frame, area: ratatui::prelude::Rect) {
+        let block = Block::bordered()
+            .title(
+                Line::from(format!("转换报告（{}条）", self.warnings.len()))
+                    .style(THEME_CONFIG.basic.border_title)
+                    .centered(),
+            )
+            .title_bottom(Line::from("按R返回书源列表").style(THEME_CONFIG.basic.border_info))
+            .border_style(THEME_CONFIG.basic.border)
+            .padding(Padding::horizontal(1));
+
+        let lines = self
+            .warnings
+            .iter()
+            .map(|warning| Line::from(warning.to_string()).style(THEME_CONFIG.basic.text))
+            .collect::<Vec<_>>();
+
+        frame.render_widget(
+            Paragraph::new(lines)
+                .wrap(Wrap { trim: false })
+                .scroll((self.report_scroll, 0))
+                .block(block),
+            area,
+        );
+    }
+
     fn render_list(&mut self, frame: &mut ratatui::Frame, area: ratatui::prelude::Rect) {
         let list_items = self.book_sources.clone();
         let selected = self.selected.clone();
@@ -151,6 +187,11 @@ impl Component for Import {
 
         self.search.render(frame, top)?;
 
+        if self.show_report {
+            self.render_report(frame, bottom);
+            return Ok(());
+        }
+
         let len = self.book_sources.len();
         let current = self.list_state.selected.unwrap_or(0);
 
@@ -169,6 +210,14 @@ impl Component for Import {
             );
         }
 
+        if !self.warnings.is_empty() {
+            block = block.title_bottom(
+                Line::from(format!("按R查看转换报告（{}条） ", self.warnings.len()))
+                    .style(THEME_CONFIG.basic.border_info)
+                    .right_aligned(),
+            );
+        }
+
         let list_area = block.inner(bottom);
         self.render_list(frame, list_area);
 
@@ -197,7 +246,24 @@ impl Component for Import {
         if key.kind != KeyEventKind::Press {
             return Ok(Some(key));
         }
+        if self.show_report {
+            match key.code {
+                KeyCode::Char('j') | KeyCode::Down => {
+                    self.report_scroll = self.report_scroll.saturating_add(1);
+                    return Ok(None);
+                }
+                KeyCode::Char('k') | KeyCode::Up => {
+                    self.report_scroll = self.report_scroll.saturating_sub(1);
+                    return Ok(None);
+                }
+                _ => {}
+            }
+        }
         match key.code {
+            KeyCode::Char('r') if !self.warnings.is_empty() => {
+                self.show_report = !self.show_report;
+                Ok(None)
+            }
             KeyCode::Char('j') | KeyCode::Down => {
                 self.list_state.next();
                 Ok(None)
@@ -263,6 +329,7 @@ impl Component for Import {
             ("解析书源/确定选择", "Enter"),
             ("移动", "J / ▼ / K / ▲"),
             ("选择", "Space"),
+            ("查看/隐藏转换报告", "R"),
             ("切换到书源管理", "Tab"),
         ])
     }
@@ -4,6 +4,7 @@ use crate::{
     components::{Component, Confirm, ConfirmState, Empty, KeyShortcutInfo},
     errors::Errors,
     pages::Page,
+    source_check::{check_book_source, CONCURRENCY},
     utils::time_to_string,
     Events, Navigator, Result, Router, THEME_CONFIG,
 };
@@ -11,23 +12,63 @@ use async_trait::async_trait;
 
 use crossterm::event::{KeyCode, KeyEvent, KeyEventKind};
 use import::Import;
-use parse_book_source::BookSource;
+use parse_book_source::{BookSource, CheckReport, ImportReport};
 use ratatui::{
+    style::Style,
     text::{Line, Text},
     widgets::{Block, Padding, Paragraph, Scrollbar, ScrollbarState},
 };
-use std::sync::Arc;
-use tokio::sync::{mpsc::Sender, Mutex};
+use std::{collections::HashMap, sync::Arc};
+use tokio::sync::{mpsc::Sender, Mutex, Semaphore};
 use tui_widget_list::{ListBuilder, ListState, ListView};
 
-use super::find_books::FindBooks;
+use super::{find_books::FindBooks, login::Login, rule_debugger::RuleDebugger};
 pub mod import;
 
 pub enum BookSourceManagerMsg {
     Error(Errors),
-    ParseResult(Vec<BookSource>),
+    ParseResult(ImportReport),
     Parse(String),
     Selected(Vec<BookSource>),
+    /// 书源检查结果，书源规则无效时为错误信息
+    CheckResult(CheckKey, std::result::Result<CheckReport, String>),
+}
+
+/// 书源检查的关键字
+const CHECK_KEYWORD: &str = "斗破苍穹";
+
+/// 书源链接和名称
+pub type CheckKey = (String, String);
+
+fn check_key(book_source: &BookSource) -> CheckKey {
+    (
+        book_source.book_source_url.clone(),
+        book_source.book_source_name.clone(),
+    )
+}
+
+#[derive(Debug, Clone)]
+pub enum CheckState {
+    Checking,
+    Done(std::result::Result<CheckReport, String>),
+}
+
+impl CheckState {
+    fn to_line(&self) -> Line<'static> {
+        match self {
+            CheckState::Checking => {
+                Line::from("检查中…").style(Style::new().fg(THEME_CONFIG.colors.warning_color))
+            }
+            CheckState::Done(Ok(report)) => match report.first_error() {
+                None => Line::from(format!("检查通过 {:.1}s", report.duration().as_secs_f64()))
+                    .style(Style::new().fg(THEME_CONFIG.colors.success_color)),
+                Some(stage) => Line::from(format!("检查失败：{}，按V查看报告", stage.stage))
+                    .style(Style::new().fg(THEME_CONFIG.colors.error_color)),
+            },
+            CheckState::Done(Err(_)) => Line::from("书源加载失败，按V查看报告")
+                .style(Style::new().fg(THEME_CONFIG.colors.error_color)),
+        }
+    }
 }
 
 pub struct BookSourceManager {
@@ -38,6 +79,10 @@ pub struct BookSourceManager {
     pub sender: Sender<BookSourceManagerMsg>,
     pub import: Import,
     pub show_import: bool,
+    /// 书源检查状态
+    pub checks: HashMap<CheckKey, CheckState>,
+    /// 限制同时检查的书源数量
+    pub check_permits: Arc<Semaphore>,
 }
 
 impl BookSourceManager {
@@ -54,11 +99,36 @@ impl BookSourceManager {
             sender,
             show_import: book_sources.clone().lock().await.is_empty(),
             book_sources,
+            checks: HashMap::new(),
+            check_permits: Arc::new(Semaphore::new(CONCURRENCY)),
+        }
+    }
+
+    /// 在后台检查书源，结果通过 [BookSourceManagerMsg::CheckResult] 返回
+    fn check(&mut self, book_sources: Vec<BookSource>) {
+        for book_source in book_sources {
+            let key = check_key(&book_source);
+            if matches!(self.checks.get(&key), Some(CheckState::Checking)) {
+                continue;
+            }
+            self.checks.insert(key.clone(), CheckState::Checking);
+
+            let sender = self.sender.clone();
+            let permits = self.check_permits.clone();
+            tokio::spawn(async move {
+                let _permit = permits.acquire().await;
+                let res = check_book_source(book_source, CHECK_KEYWORD, None).await;
+                sender
+                    .send(BookSourceManagerMsg::CheckResult(key, res))
+                    .await
+                    .ok();
+            });
         }
     }
 
     fn render_list(&mut self, frame: &mut ratatui::Frame, area: ratatui::prelude::Rect) {
         let list_items = self.book_sources.try_lock().unwrap().clone();
+        let checks = self.checks.clone();
         let length = list_items.len();
         let builder = ListBuilder::new(move |context| {
             let item = &list_items[context.index];
@@ -77,7 +147,7 @@ impl BookSourceManager {
                 THEME_CONFIG.basic.text
             };
 
-            let paragraph = Paragraph::new(Text::from(vec![
+            let mut lines = vec![
                 Line::from(item.book_source_name.clone())
                     .style(text_style)
                     .centered(),
@@ -88,8 +158,12 @@ impl BookSourceManager {
                 ))
                 .style(THEME_CONFIG.basic.border_info.patch(text_style))
                 .right_aligned(),
-            ]))
-            .block(block);
+            ];
+            if let Some(check) = checks.get(&check_key(item)) {
+                lines.push(check.to_line());
+            }
+
+            let paragraph = Paragraph::new(Text::from(lines)).block(block);
 
             (paragraph, 5)
         });
@@ -116,8 +190,8 @@ impl Page for BookSourceManager {
             BookSourceManagerMsg::Error(e) => {
                 return Err(e);
             }
-            BookSourceManagerMsg::ParseResult(book_sources) => {
-                self.import.set_book_sources(book_sources);
+            BookSourceManagerMsg::ParseResult(report) => {
+                self.import.set_report(report);
                 self.import.set_loading(false);
             }
             BookSourceManagerMsg::Parse(query) => {
@@ -125,13 +199,13 @@ impl Page for BookSourceManager {
                 let sender = self.sender.clone();
                 tokio::spawn(async move {
                     match if query.starts_with("http") {
-                        BookSource::from_url(query.trim()).await
+                        BookSource::import_from_url(query.trim()).await
                     } else {
-                        BookSource::from_path(query.trim())
+                        BookSource::import_from_path(query.trim())
                     } {
-                        Ok(book_sources) => {
+                        Ok(report) => {
                             sender
-                                .send(BookSourceManagerMsg::ParseResult(book_sources))
+                                .send(BookSourceManagerMsg::ParseResult(report))
                                 .await
                                 .unwrap();
                         }
@@ -144,11 +218,14 @@ impl Page for BookSourceManager {
                     }
                 });
             }
+            BookSourceManagerMsg::CheckResult(key, res) => {
+                self.checks.insert(key, CheckState::Done(res));
+            }
             BookSourceManagerMsg::Selected(selected_book_sources) => {
                 for i in selected_book_sources {
                     self.book_sources.lock().await.add_book_source(i);
                 }
-                self.import.set_book_sources(vec![]);
+                self.import.set_report(ImportReport::default());
                 self.show_import = false;
             }
         }
@@ -276,6 +353,45 @@ impl Component for BookSourceManager {
                     self.confirm_state.show();
                     Ok(None)
                 }
+                KeyCode::Char('c') => {
+                    let Some(index) = self.state.selected else {
+                        return Err("请选择书源".into());
+                    };
+                    let book_source = self.book_sources.lock().await[index].clone();
+                    self.check(vec![book_source]);
+                    Ok(None)
+                }
+                KeyCode::Char('C') => {
+                    let book_sources = self.book_sources.lock().await.to_vec();
+                    self.check(book_sources);
+                    Ok(None)
+                }
+                KeyCode::Char('v') => {
+                    let Some(index) = self.state.selected else {
+                        return Err("请选择书源".into());
+                    };
+                    let key = check_key(&self.book_sources.lock().await[index]);
+                    match self.checks.get(&key) {
+                        Some(CheckState::Done(Ok(report))) => Err(report.to_string().into()),
+                        Some(CheckState::Done(Err(e))) => Err(e.clone().into()),
+                        Some(CheckState::Checking) => Err("正在检查该书源".into()),
+                        None => Err("该书源还没有检查，请按C检查".into()),
+                    }
+                }
+                KeyCode::Char('r') => {
+                    self.navigator
+                        .push(Box::new(RuleDebugger::to_page_route()))?;
+                    Ok(None)
+                }
+                KeyCode::Char('u') => {
+                    let Some(index) = self.state.selected else {
+                        return Err("请选择书源".into());
+                    };
+                    let book_source = self.book_sources.lock().await[index].clone();
+                    self.navigator
+                        .push(Box::new(Login::to_page_route(book_source)))?;
+                    Ok(None)
+                }
                 KeyCode::Tab => {
                     self.show_import = !self.show_import;
                     Ok(None)
@@ -302,6 +418,11 @@ impl Component for BookSourceManager {
                 ("选择上一个", "K / ▲"),
                 ("查看书源", "L / ► / Enter"),
                 ("删除书源", "D"),
+                ("检查书源", "C"),
+                ("检查全部书源", "Shift + C"),
+                ("查看检查报告", "V"),
+                ("规则调试", "R"),
+                ("登录 / 清除登录状态", "U"),
                 ("切换到导入书源", "Tab"),
             ])
         }
@@ -7,7 +7,7 @@ use crate::{
 };
 use async_trait::async_trait;
 use crossterm::event::{KeyCode, KeyEvent, KeyEventKind};
-use parse_book_source::{BookList, BookSourceParser};
+use parse_book_source::{BookFilter, BookList, BookSourceParser};
 use ratatui::{
     text::{Line, Span},
     widgets::{Block, Padding, Paragraph, Scrollbar, ScrollbarState, Wrap},
@@ -18,7 +18,11 @@ use tui_widget_list::{ListBuilder, ListState, ListView};
 
 pub struct Books {
     pub state: ListState,
+    /// 筛选之后显示的书籍
     pub books: Option<BookList>,
+    /// 当前页的全部书籍
+    pub all_books: Option<BookList>,
+    pub filter: BookFilter,
     pub title: String,
     pub empty_tip: String,
     pub loading: Loading,
@@ -30,7 +34,29 @@ pub struct Books {
 
 impl Books {
     pub fn set_books(&mut self, books: BookList) {
-        self.books = Some(books);
+        self.all_books = Some(books);
+        self.apply_filter();
+    }
+
+    pub fn clear_books(&mut self) {
+        self.all_books = None;
+        self.books = None;
+    }
+
+    pub fn set_filter(&mut self, filter: &str) {
+        self.filter = BookFilter::parse(filter);
+        self.apply_filter();
+        self.state.select(None);
+    }
+
+    fn apply_filter(&mut self) {
+        self.books = self.all_books.as_ref().map(|books| {
+            books
+                .iter()
+                .filter(|book| self.filter.matches(&book.book_info))
+                .cloned()
+                .collect()
+        });
     }
 
     pub fn new(
@@ -44,6 +70,8 @@ impl Books {
         Self {
             state: ListState::default(),
             books: None,
+            all_books: None,
+            filter: BookFilter::default(),
             title: title.to_string(),
             empty_tip: empty_tip.to_string(),
             loading,
@@ -77,15 +105,19 @@ impl Books {
             .border_style(THEME_CONFIG.basic.border);
 
         if let Some(books) = self.books.as_ref() {
+            let mut info = format!(
+                " 第{}页 {}/{}",
+                self.page,
+                self.state.selected.unwrap_or(0) + 1,
+                books.len()
+            );
+            if let (false, Some(all_books)) = (self.filter.is_empty(), &self.all_books) {
+                info.push_str(&format!(" 已筛选，共{}本", all_books.len()));
+            }
             block = block.title_bottom(
-                Line::from(format!(
-                    " 第{}页 {}/{}",
-                    self.page,
-                    self.state.selected.unwrap_or(0) + 1,
-                    books.len()
-                ))
-                .style(THEME_CONFIG.basic.border_info)
-                .left_aligned(),
+                Line::from(info)
+                    .style(THEME_CONFIG.basic.border_info)
+                    .left_aligned(),
             );
         }
 
@@ -94,6 +126,9 @@ impl Books {
         if self.is_loading {
             frame.render_widget(&self.loading, inner_area);
             frame.render_widget(block, area);
+        } else if self.books.as_ref().is_some_and(|books| books.is_empty()) {
+            frame.render_widget(Empty::new("没有符合筛选条件的书籍"), inner_area);
+            frame.render_widget(block, area);
         } else if let Some(books) = &self.books {
             let list_items = books.clone();
 
@@ -145,6 +180,26 @@ impl Books {
                     );
                 }
 
+                if !item.book_info.status.is_empty() {
+                    text.push(
+                        Line::from(vec![
+                            Span::from("状态：").style(THEME_CONFIG.basic.border_info),
+                            Span::from(item.book_info.status),
+                        ])
+                        .style(text_style),
+                    );
+                }
+
+                if !item.book_info.tags.is_empty() {
+                    text.push(
+                        Line::from(vec![
+                            Span::from("标签：").style(THEME_CONFIG.basic.border_info),
+                            Span::from(item.book_info.tags.join("、")),
+                        ])
+                        .style(text_style),
+                    );
+                }
+
                 if !item.book_info.last_chapter.is_empty() {
                     text.push(
                         Line::from(vec![
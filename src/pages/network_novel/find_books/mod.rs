@@ -1,5 +1,6 @@
 use crate::{
     app::State,
+    book_source_parser,
     components::{Component, KeyShortcutInfo, Loading, Search},
     errors::Errors,
     pages::{Page, PageWrapper},
@@ -22,6 +23,7 @@ pub use select_explore::*;
 pub enum FindBooksMsg {
     Init(ExploreList),
     Search(String),
+    Filter(String),
     SelectExplore(ExploreItem),
     BookList(BookList),
     Error(Errors),
@@ -37,6 +39,8 @@ pub struct FindBooks<'a> {
     pub book_source_parser: Arc<Mutex<BookSourceParser>>,
     pub explore: Option<SelectExplore<'a>>,
     pub search: Search<'a>,
+    /// 筛选当前页的书籍，见 [parse_book_source::BookFilter]
+    pub filter: Search<'a>,
     pub book_list: Books,
     pub navigator: crate::Navigator,
     pub sender: Sender<FindBooksMsg>,
@@ -75,9 +79,19 @@ impl Page<BookSourceParser> for FindBooks<'_> {
             |_| (true, ""),
         );
 
+        let sender_clone = sender.clone();
+        let filter = Search::new(
+            "筛选，例如 状态:完结 标签:系统 评分>=8",
+            move |filter| {
+                sender_clone.try_send(FindBooksMsg::Filter(filter)).unwrap();
+            },
+            |_| (true, ""),
+        );
+
         Ok(Self {
             explore: None,
             search,
+            filter,
             book_list: Books::new(
                 navigator.clone(),
                 "搜索结果",
@@ -131,6 +145,9 @@ impl Page<BookSourceParser> for FindBooks<'_> {
                 self.book_list.page = 1;
                 self.get_book_list();
             }
+            FindBooksMsg::Filter(filter) => {
+                self.book_list.set_filter(&filter);
+            }
             FindBooksMsg::SelectExplore(explore) => {
                 self.current_explore = Some(explore.clone());
                 self.current = Some(Current::Explore);
@@ -141,7 +158,7 @@ impl Page<BookSourceParser> for FindBooks<'_> {
             FindBooksMsg::BookList(book_list) => {
                 self.book_list.is_loading = false;
                 if book_list.is_empty() {
-                    self.book_list.books = None;
+                    self.book_list.clear_books();
                 } else {
                     self.book_list.set_books(book_list);
                 }
@@ -160,7 +177,7 @@ impl FindBooks<'_> {
     pub fn to_page_route(
         book_source: BookSource,
     ) -> Result<PageWrapper<FindBooks<'static>, BookSourceParser, FindBooksMsg>> {
-        let json_source = BookSourceParser::try_from(book_source)?;
+        let json_source = book_source_parser(book_source)?;
         Ok(PageWrapper::new(json_source, None))
     }
 
@@ -236,7 +253,10 @@ impl Component for FindBooks<'_> {
         let [top, content] =
             Layout::vertical([Constraint::Length(3), Constraint::Fill(1)]).areas(area);
 
-        self.search.render(frame, top)?;
+        let [search, filter] =
+            Layout::horizontal([Constraint::Fill(3), Constraint::Fill(2)]).areas(top);
+        self.search.render(frame, search)?;
+        self.filter.render(frame, filter)?;
         self.book_list.render(frame, content)?;
 
         if let Some(explore) = &mut self.explore {
@@ -264,6 +284,10 @@ impl Component for FindBooks<'_> {
                 self.get_book_list();
                 Ok(None)
             }
+            KeyCode::Char('f') => {
+                self.filter.is_focus = true;
+                Ok(None)
+            }
             _ => Ok(Some(key)),
         }
     }
@@ -273,6 +297,10 @@ impl Component for FindBooks<'_> {
         events: crate::Events,
         state: State,
     ) -> crate::Result<Option<crate::Events>> {
+        if self.filter.is_focus {
+            return self.filter.handle_events(events, state).await;
+        }
+
         let Some(events) = self.search.handle_events(events, state.clone()).await? else {
             return Ok(None);
         };
@@ -307,6 +335,7 @@ impl Component for FindBooks<'_> {
             ("选择上一个书籍", "K / ▲"),
             ("下一页", "L / ►"),
             ("上一页", "H / ◄"),
+            ("筛选当前页书籍", "F"),
         ]);
 
         if let Some(explore) = &self.explore {
@@ -0,0 +1,282 @@
+use crate::{
+    app::State,
+    book_source_parser,
+    components::{Component, KeyShortcutInfo, Loading, Search},
+    pages::{Page, PageWrapper},
+    Events, Navigator, Result, Router, THEME_CONFIG,
+};
+use async_trait::async_trait;
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind};
+use parse_book_source::{BookSource, BookSourceParser};
+use ratatui::{
+    layout::{Constraint, Layout},
+    style::Style,
+    text::Line,
+    widgets::{Block, Padding, Paragraph, Wrap},
+};
+use std::sync::Arc;
+use tokio::sync::{mpsc::Sender, Mutex};
+
+pub enum LoginMsg {
+    /// 账号输入完成，切换到密码
+    Next,
+    Login,
+    /// 登录结果，成功时为保存的 cookie 数量
+    LoginResult(std::result::Result<usize, String>),
+}
+
+/// 书源登录页面，输入账号和密码登录，也可以清除保存的登录状态
+pub struct Login<'a> {
+    pub username: Search<'a>,
+    pub password: Search<'a>,
+    pub parser: Arc<Mutex<BookSourceParser>>,
+    pub sender: Sender<LoginMsg>,
+    pub book_source_name: String,
+    pub need_login: bool,
+    pub cookie_count: usize,
+    /// 最近一次操作的结果，是否成功和提示信息
+    pub message: Option<(bool, String)>,
+    pub loading: Loading,
+    pub is_loading: bool,
+}
+
+impl Login<'_> {
+    pub fn to_page_route(
+        book_source: BookSource,
+    ) -> PageWrapper<Login<'static>, BookSource, LoginMsg> {
+        PageWrapper::new(book_source, Some(4))
+    }
+
+    fn login(&mut self) {
+        if !self.need_login {
+            self.message = Some((false, "该书源没有设置 loginUrl，不需要登录".into()));
+            return;
+        }
+
+        let username = self.username.get_value().trim().to_string();
+        let password = self.password.get_value().to_string();
+        if username.is_empty() {
+            self.username.is_focus = true;
+            self.message = Some((false, "请输入账号".into()));
+            return;
+        }
+
+        self.is_loading = true;
+        self.message = None;
+        let parser = self.parser.clone();
+        let sender = self.sender.clone();
+        tokio::spawn(async move {
+            let mut parser = parser.lock().await;
+            let res = match parser.login(&username, &password).await {
+                Ok(_) => Ok(parser.http_client.cookie_count()),
+                Err(e) => Err(e.to_string()),
+            };
+            sender.send(LoginMsg::LoginResult(res)).await.ok();
+        });
+    }
+
+    async fn logout(&mut self) -> Result<()> {
+        self.parser.lock().await.logout()?;
+        self.cookie_count = 0;
+        self.message = Some((true, "已清除登录状态".into()));
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Page<BookSource> for Login<'_> {
+    type Msg = LoginMsg;
+
+    async fn init(
+        book_source: BookSource,
+        sender: Sender<Self::Msg>,
+        _navigator: Navigator,
+        _state: State,
+    ) -> Result<Self> {
+        let book_source_name = book_source.book_source_name.clone();
+        let parser = book_source_parser(book_source)?;
+
+        let sender_clone = sender.clone();
+        let mut username = Search::new(
+            "请输入账号",
+            move |_| {
+                sender_clone.try_send(LoginMsg::Next).ok();
+            },
+            |_| (true, ""),
+        );
+        username.is_focus = parser.need_login();
+
+        let sender_clone = sender.clone();
+        let mut password = Search::new(
+            "请输入密码",
+            move |_| {
+                sender_clone.try_send(LoginMsg::Login).ok();
+            },
+            |_| (true, ""),
+        );
+        password.textarea.set_mask_char('*');
+
+        Ok(Self {
+            username,
+            password,
+            sender,
+            book_source_name,
+            need_login: parser.need_login(),
+            cookie_count: parser.http_client.cookie_count(),
+            parser: Arc::new(Mutex::new(parser)),
+            message: None,
+            loading: Loading::new("登录中..."),
+            is_loading: false,
+        })
+    }
+
+    async fn update(&mut self, msg: Self::Msg) -> Result<()> {
+        match msg {
+            LoginMsg::Next => self.password.is_focus = true,
+            LoginMsg::Login => self.login(),
+            LoginMsg::LoginResult(res) => {
+                self.is_loading = false;
+                match res {
+                    Ok(count) => {
+                        self.cookie_count = count;
+                        self.message = Some((true, "登录成功".into()));
+                    }
+                    Err(e) => self.message = Some((false, e)),
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Router for Login<'_> {}
+
+#[async_trait]
+impl Component for Login<'_> {
+    fn render(&mut self, frame: &mut ratatui::Frame, area: ratatui::prelude::Rect) -> Result<()> {
+        let [username_area, password_area, status_area] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Length(3),
+            Constraint::Fill(1),
+        ])
+        .areas(area);
+
+        self.username.render(frame, username_area)?;
+        self.password.render(frame, password_area)?;
+
+        let block = Block::bordered()
+            .title(
+                Line::from(format!("登录 - {}", self.book_source_name))
+                    .style(THEME_CONFIG.basic.border_title)
+                    .centered(),
+            )
+            .border_style(THEME_CONFIG.basic.border)
+            .padding(Padding::uniform(1));
+
+        if self.is_loading {
+            let inner = block.inner(status_area);
+            frame.render_widget(block, status_area);
+            frame.render_widget(&self.loading, inner);
+            return Ok(());
+        }
+
+        let mut lines = vec![];
+        if !self.need_login {
+            lines.push(Line::from("该书源没有设置 loginUrl，不需要登录").style(THEME_CONFIG.empty));
+        }
+        lines.push(
+            Line::from(if self.cookie_count > 0 {
+                format!("已保存 {} 个 cookie", self.cookie_count)
+            } else {
+                "未登录".to_string()
+            })
+            .style(THEME_CONFIG.basic.text),
+        );
+
+        if let Some((success, message)) = &self.message {
+            let style = Style::new().fg(if *success {
+                THEME_CONFIG.colors.success_color
+            } else {
+                THEME_CONFIG.colors.error_color
+            });
+            lines.push(Line::from(""));
+            lines.extend(
+                message
+                    .lines()
+                    .map(|line| Line::from(line.to_string()).style(style)),
+            );
+        }
+
+        frame.render_widget(
+            Paragraph::new(lines)
+                .wrap(Wrap { trim: false })
+                .block(block),
+            status_area,
+        );
+        Ok(())
+    }
+
+    async fn handle_tick(&mut self, _state: State) -> Result<()> {
+        if self.is_loading {
+            self.loading.state.calc_next();
+        }
+        Ok(())
+    }
+
+    async fn handle_key_event(&mut self, key: KeyEvent, _state: State) -> Result<Option<KeyEvent>> {
+        if key.kind != KeyEventKind::Press {
+            return Ok(Some(key));
+        }
+        match key.code {
+            KeyCode::Char('u') => {
+                self.username.is_focus = true;
+                Ok(None)
+            }
+            KeyCode::Char('p') => {
+                self.password.is_focus = true;
+                Ok(None)
+            }
+            KeyCode::Enter => {
+                self.login();
+                Ok(None)
+            }
+            KeyCode::Char('d') => {
+                self.logout().await?;
+                Ok(None)
+            }
+            _ => Ok(Some(key)),
+        }
+    }
+
+    async fn handle_events(&mut self, events: Events, state: State) -> Result<Option<Events>> {
+        // 两个输入框只把事件交给获得焦点的那个
+        if self.username.is_focus {
+            return self.username.handle_events(events, state).await;
+        }
+        if self.password.is_focus {
+            return self.password.handle_events(events, state).await;
+        }
+
+        match events {
+            Events::KeyEvent(key) => self
+                .handle_key_event(key, state)
+                .await
+                .map(|item| item.map(Events::KeyEvent)),
+            Events::Tick => {
+                self.handle_tick(state).await?;
+                Ok(Some(Events::Tick))
+            }
+            other => Ok(Some(other)),
+        }
+    }
+
+    fn key_shortcut_info(&self) -> KeyShortcutInfo {
+        KeyShortcutInfo::new(vec![
+            ("输入账号", "U"),
+            ("输入密码", "P"),
+            ("登录", "Enter"),
+            ("清除登录状态", "D"),
+            ("退出输入", "ESC"),
+        ])
+    }
+}
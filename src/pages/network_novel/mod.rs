@@ -1,6 +1,8 @@
 pub mod book_detail;
 pub mod book_source_manager;
 pub mod find_books;
+pub mod login;
+pub mod rule_debugger;
 use super::PageWrapper;
 use crate::{Result, RoutePage};
 use book_source_manager::{BookSourceManager, BookSourceManagerMsg};
@@ -0,0 +1,363 @@
+use crate::{
+    app::State,
+    components::{Component, KeyShortcutInfo, Loading, Search},
+    errors::Errors,
+    http_config,
+    pages::{Page, PageWrapper},
+    Events, Navigator, Result, Router, THEME_CONFIG,
+};
+use async_trait::async_trait;
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind};
+use parse_book_source::{AnalyzerManager, HttpClient};
+use ratatui::{
+    layout::{Constraint, Layout},
+    style::Style,
+    text::Line,
+    widgets::{Block, Padding, Paragraph, Wrap},
+};
+use std::path::Path;
+use tokio::sync::mpsc::Sender;
+
+/// 每一步最多显示的元素数量
+const MAX_ELEMENTS: usize = 20;
+/// 每个元素最多显示的字符数
+const MAX_ELEMENT_LENGTH: usize = 200;
+
+pub enum RuleDebuggerMsg {
+    /// 加载链接、文件或者直接使用输入的内容
+    Load(String),
+    /// 加载完成，文档来源和内容
+    Loaded(String, String),
+    Error(Errors),
+}
+
+/// 规则调试页面，输入规则时实时显示每一步的结果
+pub struct RuleDebugger<'a> {
+    pub source: Search<'a>,
+    pub rule: Search<'a>,
+    pub sender: Sender<RuleDebuggerMsg>,
+    pub analyzer: AnalyzerManager,
+    /// 文档来源，链接、文件路径或者“输入内容”
+    pub document_name: String,
+    pub document: String,
+    /// 上一次执行的规则
+    pub last_rule: String,
+    pub output: Vec<Line<'static>>,
+    pub scroll: u16,
+    pub loading: Loading,
+    pub is_loading: bool,
+}
+
+impl RuleDebugger<'_> {
+    pub fn to_page_route() -> PageWrapper<RuleDebugger<'static>, (), RuleDebuggerMsg> {
+        PageWrapper::new((), Some(4))
+    }
+
+    fn truncate(content: &str) -> String {
+        let content = content.split_whitespace().collect::<Vec<_>>().join(" ");
+        if content.chars().count() > MAX_ELEMENT_LENGTH {
+            format!(
+                "{}…",
+                content.chars().take(MAX_ELEMENT_LENGTH).collect::<String>()
+            )
+        } else {
+            content
+        }
+    }
+
+    /// 执行规则，依次显示每一步的结果和最终的字符串结果
+    fn evaluate(&mut self) {
+        let rule = self.rule.get_value().trim().to_string();
+        self.last_rule = rule.clone();
+        self.scroll = 0;
+        self.output.clear();
+
+        if rule.is_empty() || self.document.is_empty() {
+            return;
+        }
+
+        let text = THEME_CONFIG.basic.text;
+        let info = THEME_CONFIG.basic.border_info;
+        let error = Style::new().fg(THEME_CONFIG.colors.error_color);
+
+        for (index, step) in self
+            .analyzer
+            .debug(&rule, &self.document)
+            .iter()
+            .enumerate()
+        {
+            let analyzer = step
+                .analyzer
+                .as_ref()
+                .map(|analyzer| format!("{:?}", analyzer))
+                .unwrap_or("Js".to_string());
+
+            match &step.result {
+                Ok(elements) => {
+                    self.output.push(
+                        Line::from(format!(
+                            "{}. [{}] {}  → {} 个结果",
+                            index + 1,
+                            analyzer,
+                            step.rule,
+                            elements.len()
+                        ))
+                        .style(THEME_CONFIG.basic.border_title),
+                    );
+                    for element in elements.iter().take(MAX_ELEMENTS) {
+                        self.output.push(
+                            Line::from(format!("   {}", Self::truncate(element))).style(text),
+                        );
+                    }
+                    if elements.len() > MAX_ELEMENTS {
+                        self.output.push(
+                            Line::from(format!("   … 还有 {} 个", elements.len() - MAX_ELEMENTS))
+                                .style(info),
+                        );
+                    }
+                }
+                Err(e) => {
+                    self.output.push(
+                        Line::from(format!("{}. [{}] {}", index + 1, analyzer, step.rule))
+                            .style(error),
+                    );
+                    for line in e.lines() {
+                        self.output
+                            .push(Line::from(format!("   {}", line)).style(error));
+                    }
+                }
+            }
+        }
+
+        self.output.push(Line::from(""));
+        match self.analyzer.get_string(&rule, &self.document, None) {
+            Ok(result) => {
+                self.output
+                    .push(Line::from("字符串结果").style(THEME_CONFIG.basic.border_title));
+                for line in result.lines() {
+                    self.output.push(Line::from(line.to_string()).style(text));
+                }
+            }
+            Err(e) => {
+                self.output.push(Line::from("字符串结果").style(error));
+                for line in e.to_string().lines() {
+                    self.output.push(Line::from(line.to_string()).style(error));
+                }
+            }
+        }
+    }
+
+    fn load(&mut self, query: String) {
+        let sender = self.sender.clone();
+        let query = query.trim().to_string();
+
+        if query.starts_with("http") {
+            self.is_loading = true;
+            tokio::spawn(async move {
+                let res = match HttpClient::new(&query, http_config()) {
+                    Ok(client) => client.get_page(&query).await,
+                    Err(e) => Err(e),
+                };
+                let msg = match res {
+                    Ok(page) => RuleDebuggerMsg::Loaded(page.url, page.text),
+                    Err(e) => RuleDebuggerMsg::Error(e.into()),
+                };
+                sender.send(msg).await.ok();
+            });
+        } else if Path::new(&query).is_file() {
+            let msg = match std::fs::read_to_string(&query) {
+                Ok(content) => RuleDebuggerMsg::Loaded(query, content),
+                Err(e) => RuleDebuggerMsg::Error(e.into()),
+            };
+            sender.try_send(msg).ok();
+        } else {
+            sender
+                .try_send(RuleDebuggerMsg::Loaded("输入内容".to_string(), query))
+                .ok();
+        }
+    }
+}
+
+#[async_trait]
+impl Page for RuleDebugger<'_> {
+    type Msg = RuleDebuggerMsg;
+
+    async fn init(
+        _arg: (),
+        sender: Sender<Self::Msg>,
+        _navigator: Navigator,
+        _state: State,
+    ) -> Result<Self> {
+        let sender_clone = sender.clone();
+        let mut source = Search::new(
+            "请输入链接、文件路径或文档内容",
+            move |query| {
+                sender_clone.try_send(RuleDebuggerMsg::Load(query)).ok();
+            },
+            |_| (true, ""),
+        );
+        source.is_focus = true;
+
+        let rule = Search::new("请输入规则，例如 @css:li@text", |_| {}, |_| (true, ""));
+
+        Ok(Self {
+            source,
+            rule,
+            sender,
+            analyzer: AnalyzerManager::new()?,
+            document_name: String::new(),
+            document: String::new(),
+            last_rule: String::new(),
+            output: vec![],
+            scroll: 0,
+            loading: Loading::new("加载中..."),
+            is_loading: false,
+        })
+    }
+
+    async fn update(&mut self, msg: Self::Msg) -> Result<()> {
+        match msg {
+            RuleDebuggerMsg::Load(query) => self.load(query),
+            RuleDebuggerMsg::Loaded(name, content) => {
+                self.is_loading = false;
+                self.document_name = name;
+                self.document = content;
+                self.rule.is_focus = true;
+                self.evaluate();
+            }
+            RuleDebuggerMsg::Error(e) => {
+                self.is_loading = false;
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Router for RuleDebugger<'_> {}
+
+#[async_trait]
+impl Component for RuleDebugger<'_> {
+    fn render(&mut self, frame: &mut ratatui::Frame, area: ratatui::prelude::Rect) -> Result<()> {
+        let [source_area, rule_area, output_area] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Length(3),
+            Constraint::Fill(1),
+        ])
+        .areas(area);
+
+        self.source.render(frame, source_area)?;
+        self.rule.render(frame, rule_area)?;
+
+        let title = if self.document.is_empty() {
+            "规则调试".to_string()
+        } else {
+            format!(
+                "规则调试 - {}（{} 字符）",
+                self.document_name,
+                self.document.chars().count()
+            )
+        };
+        let block = Block::bordered()
+            .title(
+                Line::from(title)
+                    .style(THEME_CONFIG.basic.border_title)
+                    .centered(),
+            )
+            .border_style(THEME_CONFIG.basic.border)
+            .padding(Padding::horizontal(1));
+
+        if self.is_loading {
+            let inner = block.inner(output_area);
+            frame.render_widget(block, output_area);
+            frame.render_widget(&self.loading, inner);
+            return Ok(());
+        }
+
+        let tip = if self.document.is_empty() {
+            "按S输入链接、文件路径或文档内容"
+        } else {
+            "按R输入规则，输入时实时显示每一步的结果"
+        };
+        let paragraph = if self.output.is_empty() {
+            Paragraph::new(Line::from(tip).style(THEME_CONFIG.empty).centered())
+        } else {
+            Paragraph::new(self.output.clone()).scroll((self.scroll, 0))
+        };
+
+        frame.render_widget(
+            paragraph.wrap(Wrap { trim: false }).block(block),
+            output_area,
+        );
+        Ok(())
+    }
+
+    async fn handle_tick(&mut self, _state: State) -> Result<()> {
+        if self.is_loading {
+            self.loading.state.calc_next();
+        }
+        Ok(())
+    }
+
+    async fn handle_key_event(&mut self, key: KeyEvent, _state: State) -> Result<Option<KeyEvent>> {
+        if key.kind != KeyEventKind::Press {
+            return Ok(Some(key));
+        }
+        match key.code {
+            KeyCode::Char('s') => {
+                self.source.is_focus = true;
+                Ok(None)
+            }
+            KeyCode::Char('r') => {
+                self.rule.is_focus = true;
+                Ok(None)
+            }
+            KeyCode::Char('j') | KeyCode::Down => {
+                self.scroll = self.scroll.saturating_add(1);
+                Ok(None)
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.scroll = self.scroll.saturating_sub(1);
+                Ok(None)
+            }
+            _ => Ok(Some(key)),
+        }
+    }
+
+    async fn handle_events(&mut self, events: Events, state: State) -> Result<Option<Events>> {
+        // 两个输入框只把事件交给获得焦点的那个
+        if self.source.is_focus {
+            return self.source.handle_events(events, state).await;
+        }
+
+        if self.rule.is_focus {
+            let res = self.rule.handle_events(events, state).await;
+            if self.rule.get_value().trim() != self.last_rule {
+                self.evaluate();
+            }
+            return res;
+        }
+
+        match events {
+            Events::KeyEvent(key) => self
+                .handle_key_event(key, state)
+                .await
+                .map(|item| item.map(Events::KeyEvent)),
+            Events::Tick => {
+                self.handle_tick(state).await?;
+                Ok(Some(Events::Tick))
+            }
+            other => Ok(Some(other)),
+        }
+    }
+
+    fn key_shortcut_info(&self) -> KeyShortcutInfo {
+        KeyShortcutInfo::new(vec![
+            ("输入链接、文件路径或文档内容", "S"),
+            ("输入规则", "R"),
+            ("加载文档", "Enter"),
+            ("退出输入", "ESC"),
+            ("滚动结果", "J / ▼ / K / ▲"),
+        ])
+    }
+}
@@ -2,7 +2,7 @@ use crate::{
     app::State,
     components::{Component, KeyShortcutInfo, Loading},
     errors::Errors,
-    novel::Novel,
+    novel::{ChapterKind, Novel},
     pages::{Page, PageWrapper},
     Events, Navigator, Result, Router,
 };
@@ -16,6 +16,12 @@ use tokio::sync::mpsc;
 pub mod read_content;
 pub mod select_chapter;
 
+/// 所有章节的类型，用于在目录中分组显示分卷
+fn chapter_kinds<T: Novel>(novel: &T) -> Vec<ChapterKind> {
+    let len = novel.get_chapters().map(Vec::len).unwrap_or_default();
+    (0..len).map(|index| novel.chapter_kind(index)).collect()
+}
+
 pub enum ReadNovelMsg<T: Novel + 'static> {
     Next,
     Prev,
@@ -115,6 +121,7 @@ where
                 if let Ok(chapters) = novel.get_chapters_names() {
                     self.chapters = chapters.clone();
                     self.select_chapter.set_total_chapters(chapters.len());
+                    self.select_chapter.set_kinds(chapter_kinds(&novel));
                     self.select_chapter
                         .set_list(chapters, Some(novel.current_chapter));
                 }
@@ -145,6 +152,8 @@ where
 
                 self.chapters = chapters.clone();
                 self.select_chapter.set_total_chapters(chapters.len());
+                self.select_chapter
+                    .set_kinds(chapter_kinds(self.novel.as_ref().unwrap()));
                 self.select_chapter
                     .set_list(chapters, Some(self.novel.as_mut().unwrap().current_chapter));
 
@@ -196,7 +205,7 @@ where
                 self.read_content
                     .set_content(content, self.init_line_percent.take());
 
-                if let Ok(chapter_name) = self.novel.as_ref().unwrap().get_current_chapter_name() {
+                if let Ok(chapter_name) = self.novel.as_ref().unwrap().get_current_chapter_title() {
                     self.read_content.set_current_chapter(chapter_name);
                 }
 
@@ -337,8 +346,10 @@ where
                 ("搜索章节", "S"),
                 ("选择下一个", "J / ▼"),
                 ("选择上一个", "K / ▲"),
+                ("收起分卷", "H / ◄"),
+                ("展开分卷", "L / ►"),
                 ("切换阅读模式", "Tab / Esc"),
-                ("阅读选中章节", "Enter"),
+                ("阅读选中章节 / 收起展开分卷", "Enter"),
             ]
         } else {
             vec![
@@ -2,16 +2,18 @@ use super::ReadNovelMsg;
 use crate::{
     app::State,
     components::{Component, Empty, Search},
-    novel::Novel,
+    novel::{ChapterKind, Novel},
     Result, THEME_CONFIG,
 };
 use async_trait::async_trait;
 use crossterm::event::{KeyCode, KeyEvent};
 use ratatui::{
     layout::{Constraint, Layout},
-    text::Line,
+    style::{Style, Stylize},
+    text::{Line, Span},
     widgets::{Block, List, ListState, Padding, Scrollbar, ScrollbarState},
 };
+use std::collections::HashSet;
 use tokio::sync::mpsc;
 
 pub struct SelectChapter<'a, T>
@@ -19,7 +21,16 @@ where
     T: Novel + Send + Sync + 'static,
 {
     pub state: ListState,
+    /// 全部章节或者搜索结果
     pub chapters: Vec<(String, usize)>,
+    /// 按章节索引的章节类型
+    pub kinds: Vec<ChapterKind>,
+    /// 按章节索引的所属分卷，值为分卷标题的章节索引
+    pub volumes: Vec<Option<usize>>,
+    /// 收起的分卷
+    pub collapsed: HashSet<usize>,
+    /// 实际显示的章节，收起的分卷中的章节不显示
+    pub visible: Vec<(String, usize)>,
     pub list: List<'a>,
     pub search: Search<'a>,
     pub sender: mpsc::Sender<ReadNovelMsg<T>>,
@@ -39,18 +50,18 @@ where
         let sender_clone = sender.clone();
         let chapters = chapters.unwrap_or_default();
 
-        // 创建时指点当前选择的章节
-        let mut state = ListState::default();
-        state.select(Some(current_chapter));
-
-        Self {
-            scrollbar_state: ScrollbarState::new(chapters.len()),
-            list: List::new(chapters.iter().map(|i| i.0.clone()))
+        let mut select_chapter = Self {
+            scrollbar_state: ScrollbarState::default(),
+            list: List::default()
                 .style(THEME_CONFIG.basic.text)
                 .highlight_style(THEME_CONFIG.selected),
-            state,
+            state: ListState::default(),
             total_chapters: chapters.len(),
-            chapters,
+            chapters: vec![],
+            kinds: vec![],
+            volumes: vec![],
+            collapsed: HashSet::new(),
+            visible: vec![],
             search: Search::new(
                 "搜索章节,以$开头输入数字表示索引",
                 move |query| {
@@ -61,22 +72,144 @@ where
                 |_| (true, ""),
             ),
             sender,
-        }
+        };
+        // 创建时指点当前选择的章节
+        select_chapter.set_list(chapters, Some(current_chapter));
+        select_chapter
     }
 
     pub fn set_total_chapters(&mut self, total_chapters: usize) {
         self.total_chapters = total_chapters;
     }
 
+    /// 设置章节类型，分卷标题之后、下一个分卷标题之前的章节属于这个分卷
+    pub fn set_kinds(&mut self, kinds: Vec<ChapterKind>) {
+        let mut volume = None;
+        self.volumes = kinds
+            .iter()
+            .enumerate()
+            .map(|(index, kind)| {
+                if *kind == ChapterKind::Volume {
+                    volume = Some(index);
+                    None
+                } else {
+                    volume
+                }
+            })
+            .collect();
+        self.kinds = kinds;
+        self.collapsed.clear();
+    }
+
+    fn kind(&self, index: usize) -> ChapterKind {
+        self.kinds.get(index).copied().unwrap_or_default()
+    }
+
+    /// `selected` 为选中的章节索引
     pub fn set_list(&mut self, chapters: Vec<(String, usize)>, selected: Option<usize>) {
-        self.state = ListState::default();
-        self.state.select(selected);
-        self.scrollbar_state = ScrollbarState::new(chapters.len());
-        self.list = self
-            .list
-            .clone()
-            .items(chapters.iter().map(|i| i.0.clone()));
         self.chapters = chapters;
+        self.refresh(selected);
+    }
+
+    /// 重新计算显示的章节，选中的章节所在分卷收起时选中分卷标题
+    fn refresh(&mut self, selected: Option<usize>) {
+        // 搜索结果中没有分卷标题时，章节不受分卷收起的影响
+        let headers = self
+            .chapters
+            .iter()
+            .filter(|(_, index)| self.kind(*index) == ChapterKind::Volume)
+            .map(|(_, index)| *index)
+            .collect::<HashSet<_>>();
+        let hidden = |index: usize| {
+            self.volumes
+                .get(index)
+                .copied()
+                .flatten()
+                .is_some_and(|volume| headers.contains(&volume) && self.collapsed.contains(&volume))
+        };
+
+        self.visible = self
+            .chapters
+            .iter()
+            .filter(|(_, index)| !hidden(*index))
+            .cloned()
+            .collect();
+
+        let selected = selected.map(|index| match self.volumes.get(index).copied().flatten() {
+            Some(volume) if hidden(index) => volume,
+            _ => index,
+        });
+
+        let items = self
+            .visible
+            .iter()
+            .map(|(name, index)| {
+                let index = *index;
+                let in_volume = self.volumes.get(index).copied().flatten().is_some();
+                match self.kind(index) {
+                    ChapterKind::Volume => {
+                        let marker = if self.collapsed.contains(&index) {
+                            "▶ "
+                        } else {
+                            "▼ "
+                        };
+                        Line::from(format!("{}{}", marker, name)).bold()
+                    }
+                    kind => {
+                        let mut spans = vec![Span::raw(if in_volume {
+                            format!("  {}", name)
+                        } else {
+                            name.clone()
+                        })];
+                        if kind == ChapterKind::Vip {
+                            spans.push(Span::styled(
+                                " [VIP]",
+                                Style::new().fg(THEME_CONFIG.colors.warning_color),
+                            ));
+                        }
+                        Line::from(spans)
+                    }
+                }
+            })
+            .collect::<Vec<_>>();
+
+        self.state = ListState::default();
+        self.state.select(
+            selected.and_then(|selected| self.visible.iter().position(|(_, i)| *i == selected)),
+        );
+        self.scrollbar_state = ScrollbarState::new(self.visible.len());
+        self.list = self.list.clone().items(items);
+    }
+
+    fn selected_chapter(&self) -> Option<usize> {
+        self.state
+            .selected()
+            .and_then(|selected| self.visible.get(selected))
+            .map(|(_, index)| *index)
+    }
+
+    /// 收起或展开选中章节所在的分卷，选中的是分卷标题时收起或展开这个分卷
+    fn set_collapsed(&mut self, collapsed: Option<bool>) {
+        let Some(index) = self.selected_chapter() else {
+            return;
+        };
+        let volume = if self.kind(index) == ChapterKind::Volume {
+            index
+        } else {
+            match self.volumes.get(index).copied().flatten() {
+                Some(volume) => volume,
+                None => return,
+            }
+        };
+
+        let collapsed = collapsed.unwrap_or(!self.collapsed.contains(&volume));
+        if collapsed {
+            self.collapsed.insert(volume);
+            self.refresh(Some(volume));
+        } else {
+            self.collapsed.remove(&volume);
+            self.refresh(Some(index));
+        }
     }
 }
 
@@ -89,11 +222,7 @@ where
         let [top, content] =
             Layout::vertical([Constraint::Length(3), Constraint::Fill(1)]).areas(area);
 
-        let index = if let Some(chapter_index) = self.state.selected() {
-            self.chapters[chapter_index.min(self.chapters.len() - 1)].1 + 1
-        } else {
-            0
-        };
+        let index = self.selected_chapter().map(|index| index + 1).unwrap_or(0);
 
         let block = Block::bordered()
             .title(
@@ -144,14 +273,25 @@ where
                 self.state.select_previous();
                 Ok(None)
             }
+            KeyCode::Char('h') | KeyCode::Left => {
+                self.set_collapsed(Some(true));
+                Ok(None)
+            }
+            KeyCode::Char('l') | KeyCode::Right => {
+                self.set_collapsed(Some(false));
+                Ok(None)
+            }
             KeyCode::Enter => {
-                if let Some(chapter_index) = self.state.selected() {
-                    let index = self.chapters[chapter_index].1;
-
-                    self.sender
-                        .send(ReadNovelMsg::SelectChapter(index))
-                        .await
-                        .unwrap();
+                if let Some(index) = self.selected_chapter() {
+                    // 分卷标题没有正文，回车收起或展开
+                    if self.kind(index) == ChapterKind::Volume {
+                        self.set_collapsed(None);
+                    } else {
+                        self.sender
+                            .send(ReadNovelMsg::SelectChapter(index))
+                            .await
+                            .unwrap();
+                    }
                 }
 
                 Ok(None)
@@ -0,0 +1,90 @@
+use crossterm::style::Stylize;
+use futures::{stream, StreamExt};
+use parse_book_source::{CheckReport, Recorder, Replayer};
+use std::{path::PathBuf, sync::Arc};
+
+use crate::{book_source::BookSourceCache, book_source_parser, Result};
+
+/// 同时检查的书源数量
+pub const CONCURRENCY: usize = 4;
+
+/// 检查时录制或者回放请求，见 [parse_book_source::http_client::cassette]
+#[derive(Debug, Clone)]
+pub enum Cassette {
+    /// 把请求和响应保存到目录中
+    Record(PathBuf),
+    /// 只使用目录中录制的响应，不访问网络
+    Replay(PathBuf),
+}
+
+/// 检查单个书源，书源规则无效时返回错误信息
+pub async fn check_book_source(
+    book_source: parse_book_source::BookSource,
+    keyword: &str,
+    cassette: Option<&Cassette>,
+) -> std::result::Result<CheckReport, String> {
+    let name = book_source.book_source_name.clone();
+    match book_source_parser(book_source) {
+        Ok(mut parser) => {
+            // 检查书源时总是访问网络，不使用响应缓存
+            parser.http_client.cache = None;
+            parser.http_client = match cassette {
+                Some(Cassette::Record(dir)) => {
+                    let inner = parser.http_client.transport.clone();
+                    parser
+                        .http_client
+                        .with_transport(Arc::new(Recorder::new(inner, dir)))
+                }
+                Some(Cassette::Replay(dir)) => parser
+                    .http_client
+                    .with_transport(Arc::new(Replayer::new(dir))),
+                None => parser.http_client,
+            };
+            Ok(parser.check(keyword).await)
+        }
+        Err(e) => Err(format!("{}  加载失败\n  {}", name, e)),
+    }
+}
+
+/// `trnovel source check [name|all]`，依次检查书源并输出每个阶段的结果
+pub async fn check_book_sources(
+    name: Option<String>,
+    keyword: &str,
+    cassette: Option<Cassette>,
+) -> Result<()> {
+    let book_sources = BookSourceCache::load()?
+        .iter()
+        .filter(|item| match name.as_deref() {
+            None | Some("all") => true,
+            Some(name) => item.book_source_name == name,
+        })
+        .cloned()
+        .collect::<Vec<_>>();
+
+    if book_sources.is_empty() {
+        return Err(match name {
+            Some(name) if name != "all" => format!("没有找到书源: {}", name).into(),
+            _ => "暂无书源，请先导入书源".into(),
+        });
+    }
+
+    let total = book_sources.len();
+    let mut passed = 0;
+    let mut reports = stream::iter(book_sources)
+        .map(|book_source| check_book_source(book_source, keyword, cassette.as_ref()))
+        .buffered(CONCURRENCY);
+
+    while let Some(report) = reports.next().await {
+        match report {
+            Ok(report) if report.is_ok() => {
+                passed += 1;
+                println!("{}\n", report.to_string().green());
+            }
+            Ok(report) => println!("{}\n", report.to_string().red()),
+            Err(e) => println!("{}\n", e.red()),
+        }
+    }
+
+    println!("通过 {}/{}", passed.to_string().green().bold(), total);
+    Ok(())
+}